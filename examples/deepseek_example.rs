@@ -1,4 +1,6 @@
 use reverse_api::deepseek::client::deepseek::DeepSeekClient;
+use reverse_api::ConsoleSink;
+use std::sync::Arc;
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -26,7 +28,9 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     println!("=== DeepSeek Conversation Example ===\n");
-    let client = DeepSeekClient::new(user_token).await?;
+    let client = DeepSeekClient::new(user_token)
+        .await?
+        .with_event_sink(Arc::new(ConsoleSink));
 
     // Message 1
     let message1 = "你好，请简单介绍一下你自己";
@@ -68,6 +72,21 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .unwrap_or(&"No response".to_string())
     );
 
+    // Message 4 - Deep thinking in a new session; the reasoning is returned separately
+    let message4 = "9.11 和 9.8 哪个大？";
+    println!("USER (thinking): {}", message4);
+    let response4 = client.start_convo_with_thinking(message4, None).await?;
+    if let Some(thinking) = &response4.thinking_content {
+        println!("THINKING: {} chars", thinking.chars().count());
+    }
+    println!(
+        "DEEPSEEK: {}\n",
+        response4
+            .response
+            .as_ref()
+            .unwrap_or(&"No response".to_string())
+    );
+
     println!("=== Conversation Complete ===");
 
     Ok(())
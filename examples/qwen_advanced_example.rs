@@ -1,4 +1,6 @@
 use reverse_api::qwen::client::qwen::QwenClient;
+use reverse_api::ConsoleSink;
+use std::sync::Arc;
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -12,7 +14,7 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::fs::read_to_string(".qwen_token")?.trim().to_string()
     };
 
-    let client = QwenClient::with_token(token)?;
+    let client = QwenClient::with_token(token)?.with_event_sink(Arc::new(ConsoleSink));
 
     // Part 1: Web Search - Get current information
     println!("\n╔═══════════════════════════════════════╗");
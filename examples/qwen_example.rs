@@ -1,4 +1,6 @@
 use reverse_api::qwen::client::qwen::QwenClient;
+use reverse_api::ConsoleSink;
+use std::sync::Arc;
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -21,7 +23,7 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Try token first, then fall back to email/password
     let client = if let Ok(token) = std::env::var("QWEN_TOKEN") {
         println!("Using token authentication...\n");
-        QwenClient::with_token(token)?
+        QwenClient::with_token(token)?.with_event_sink(Arc::new(ConsoleSink))
     } else {
         eprintln!("Error: No credentials provided!");
         eprintln!("\nPlease set either:");
@@ -1,5 +1,6 @@
-use reverse_api::QwenClient;
+use reverse_api::{ConsoleSink, QwenClient};
 use std::env;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -10,7 +11,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("This example demonstrates how to use images, documents, videos, and audio in conversations.\n");
 
     // Initialize client with token
-    let client = QwenClient::with_token(token)?;
+    let client = QwenClient::with_token(token)?.with_event_sink(Arc::new(ConsoleSink));
     println!("✅ Client initialized\n");
 
     // Example 1: Image Analysis
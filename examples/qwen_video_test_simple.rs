@@ -1,5 +1,6 @@
-use reverse_api::QwenClient;
+use reverse_api::{ConsoleSink, QwenClient};
 use std::env;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -7,7 +8,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("🎬 Qwen Video Generation Test\n");
 
-    let client = QwenClient::with_token(token)?;
+    let client = QwenClient::with_token(token)?.with_event_sink(Arc::new(ConsoleSink));
     println!("✅ Client initialized\n");
 
     println!("🎥 Generating video: 一只可爱的小猫在玩耍");
@@ -19,9 +20,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             Some("16:9"),
             None,
             None,
-            |status, percent| {
-                if percent % 20 == 0 || status == "success" {
-                    println!("📊 Status: {} - {}%", status, percent);
+            |progress| {
+                if progress.status != "running" {
+                    println!("\n📊 Status: {} {}", progress.status, progress.message);
                 }
             },
         )
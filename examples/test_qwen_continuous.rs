@@ -1,11 +1,12 @@
-use reverse_api::QwenClient;
+use reverse_api::{ConsoleSink, QwenClient};
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let token = std::fs::read_to_string(".qwen_token")?;
     let token = token.trim();
 
-    let client = QwenClient::with_token(token.to_string())?;
+    let client = QwenClient::with_token(token.to_string())?.with_event_sink(Arc::new(ConsoleSink));
 
     println!("=== 测试 1: 第一条消息 ===");
     let msg1 = "我最喜欢的动物是猫";
@@ -0,0 +1,144 @@
+use super::state::AppState;
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// API keys accepted by the server.
+///
+/// With no keys configured authentication is disabled and every request is let through.
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    keys: HashSet<String>,
+    /// Leave `GET /health` reachable without a key
+    pub public_health: bool,
+}
+
+impl AuthConfig {
+    /// Collect keys from `keys_file` (one per line, `#` starts a comment)
+    /// and from the comma-separated `API_KEYS` environment variable.
+    pub fn load(keys_file: Option<&str>, public_health: bool) -> std::io::Result<Self> {
+        let mut keys = HashSet::new();
+
+        if let Some(path) = keys_file {
+            let contents = std::fs::read_to_string(path)?;
+            keys.extend(
+                contents
+                    .lines()
+                    .map(|l| l.trim())
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(|l| l.to_string()),
+            );
+        }
+
+        if let Ok(env_keys) = std::env::var("API_KEYS") {
+            keys.extend(
+                env_keys
+                    .split(',')
+                    .map(|k| k.trim())
+                    .filter(|k| !k.is_empty())
+                    .map(|k| k.to_string()),
+            );
+        }
+
+        Ok(Self {
+            keys,
+            public_health,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn accepts(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+}
+
+/// The caller of the current request, inserted into request extensions by [`require_api_key`].
+///
+/// `owner` is a fingerprint of the API key, so threads and files can be scoped to it
+/// without the key itself ever being stored. It is `None` when authentication is disabled.
+#[derive(Debug, Clone, Default)]
+pub struct ClientKey {
+    pub owner: Option<String>,
+}
+
+impl ClientKey {
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+}
+
+/// Stable, non-reversible identifier for an API key
+pub fn key_fingerprint(key: &str) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub async fn require_api_key(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let auth = state.auth_config();
+
+    let client_key = if auth.is_enabled() {
+        let provided = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|k| k.trim());
+
+        match provided {
+            None => {
+                return unauthorized(
+                    "You didn't provide an API key. You need to provide your API key in an Authorization header using Bearer auth (i.e. Authorization: Bearer YOUR_KEY).",
+                    None,
+                )
+            }
+            Some(key) if !auth.accepts(key) => {
+                return unauthorized(
+                    "Incorrect API key provided.",
+                    Some("invalid_api_key"),
+                )
+            }
+            Some(key) => ClientKey {
+                owner: Some(key_fingerprint(key)),
+            },
+        }
+    } else {
+        ClientKey::default()
+    };
+
+    request.extensions_mut().insert(client_key);
+    next.run(request).await
+}
+
+/// OpenAI-style 401 body
+fn unauthorized(message: &str, code: Option<&str>) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": "invalid_request_error",
+                "param": null,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
@@ -3,7 +3,7 @@ use axum::{extract::State, response::Html};
 
 pub async fn dashboard(State(state): State<AppState>) -> Html<String> {
     let stats = state.get_stats().await;
-    let threads = state.list_threads().await;
+    let threads = state.list_threads(None).await.unwrap_or_default();
 
     let html = format!(
         r#"<!DOCTYPE html>
@@ -222,6 +222,11 @@ pub async fn dashboard(State(state): State<AppState>) -> Html<String> {
             <div id="chartData" style="display: none;"></div>
         </div>
         
+        <div class="section">
+            <h2 class="section-title">🎬 视频任务</h2>
+            <div id="videos-container">暂无视频任务</div>
+        </div>
+        
         <div class="section">
             <h2 class="section-title">📈 实时请求</h2>
             <div id="requests-container"></div>
@@ -258,6 +263,22 @@ pub async fn dashboard(State(state): State<AppState>) -> Html<String> {
                         '</tbody></table>';
                 }}
                 
+                const videosResp = await fetch('/dashboard/videos');
+                const videos = await videosResp.json();
+                if (videos && videos.length > 0) {{
+                    document.getElementById('videos-container').innerHTML = 
+                        '<table><thead><tr><th>创建时间</th><th>任务</th><th>状态</th><th>剩余时间</th><th>更新时间</th></tr></thead><tbody>' +
+                        videos.slice(0, 10).map(v => {{
+                            const created = new Date(v.created_at * 1000).toLocaleTimeString();
+                            const updated = new Date(v.updated_at * 1000).toLocaleTimeString();
+                            const statusClass = v.status === 'failed' || v.status === 'cancelled' ? 'status-error' : 'status-success';
+                            return '<tr><td>' + created + '</td><td>' + v.id + '</td>' +
+                                   '<td><span class="status-badge ' + statusClass + '">' + v.status + '</span></td>' +
+                                   '<td>' + (v.remaining_secs != null ? v.remaining_secs + 's' : '-') + '</td><td>' + updated + '</td></tr>';
+                        }}).join('') +
+                        '</tbody></table>';
+                }}
+                
                 // 更新图表
                 if (requests && requests.length > 0) {{
                     updateChart(requests);
@@ -340,3 +361,9 @@ pub async fn dashboard_requests(
 ) -> axum::Json<Vec<super::stats::LiveRequest>> {
     axum::Json(state.get_live_requests().await)
 }
+
+pub async fn dashboard_videos(
+    State(state): State<AppState>,
+) -> axum::Json<Vec<super::video_jobs::VideoJobSummary>> {
+    axum::Json(state.video_jobs().summaries().await)
+}
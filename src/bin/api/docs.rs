@@ -293,8 +293,53 @@ pub async fn api_docs(State(_state): State<AppState>) -> Html<String> {
                 <p>为线程生成响应</p>
                 <h4>请求体</h4>
                 <div class="code-block">{
-  "thread_id": "thread-123"
+  "thread_id": "thread-123",
+  "stream": false
+}</div>
+                <div class="note">
+                    <strong>stream: true</strong> 时返回 text/event-stream，事件类型：
+                    <ul style="margin-left: 20px; margin-top: 5px;">
+                        <li><strong>phase</strong>: 进入新阶段（如深度研究的各个步骤） <code>{"phase": "..."}</code></li>
+                        <li><strong>thinking</strong>: 思考过程增量 <code>{"delta": "..."}</code></li>
+                        <li><strong>web_search</strong>: 搜索结果 <code>{"results": [...]}</code></li>
+                        <li><strong>image</strong>: 生成或编辑后的图片 <code>{"url": "..."}</code></li>
+                        <li><strong>answer</strong>: 回答增量 <code>{"delta": "..."}</code></li>
+                        <li><strong>response.completed</strong>: 完整的 Response 对象（最后一个事件）</li>
+                        <li><strong>error</strong>: 出错时返回与非流式请求相同的错误对象 <code>{"error": {"type": "...", "code": "...", "message": "..."}}</code></li>
+                    </ul>
+                </div>
+            </div>
+            
+            <h3>OpenAI 兼容接口</h3>
+            
+            <div class="endpoint">
+                <div><span class="method post">POST</span><span class="path">/v1/chat/completions</span></div>
+                <p>OpenAI Chat Completions 兼容接口，无需创建线程，每次请求携带完整消息历史</p>
+                <h4>请求体</h4>
+                <div class="code-block">{
+  "model": "qwen3-max",
+  "messages": [
+    {"role": "system", "content": "You are a helpful assistant."},
+    {"role": "user", "content": "Hello!"}
+  ],
+  "temperature": 0.7,
+  "max_tokens": 512,
+  "stream": false
 }</div>
+                <h4>响应</h4>
+                <div class="code-block">{
+  "id": "chatcmpl-xxx",
+  "object": "chat.completion",
+  "created": 1700000000,
+  "model": "qwen3-max",
+  "choices": [{
+    "index": 0,
+    "message": {"role": "assistant", "content": "Hello! How can I help?"},
+    "finish_reason": "stop"
+  }],
+  "usage": {"prompt_tokens": 12, "completion_tokens": 6, "total_tokens": 18}
+}</div>
+                <div class="note">stream 为 true 时以 SSE 返回 chat.completion.chunk，并以 data: [DONE] 结束。usage 为估算值。temperature 仅为兼容 OpenAI 而接受（须在 0 到 2 之间），上游网页接口不支持采样参数，该值会被忽略。</div>
             </div>
             
             <h3>多模态功能 (Qwen)</h3>
@@ -305,7 +350,10 @@ pub async fn api_docs(State(_state): State<AppState>) -> Html<String> {
                 <h4>请求</h4>
                 <p>Content-Type: multipart/form-data</p>
                 <div class="code-block">参数名: file
-文件类型: 图片(jpg, png), 视频(mp4), 音频(mp3, wav), 文档(txt, pdf)</div>
+文件类型: 图片(jpg, png, gif, webp, bmp, tiff, heic, avif), 视频(mp4, mov, avi, mkv, webm),
+          音频(mp3, wav, m4a, flac, ogg), 文档(pdf, doc/docx, xls/xlsx, ppt/pptx, txt, md, csv, json, 源代码)</div>
+                <div class="note">文件类型按内容（文件头）识别，无法识别时才使用扩展名；不支持的类型返回 400</div>
+                <div class="note">大小上限（可用 --upload-limits 配置）：图片 20 MiB，视频 500 MiB，音频/文档 100 MiB，超过返回 413</div>
                 <h4>响应</h4>
                 <div class="code-block">{
   "id": "file-id-uuid",
@@ -324,39 +372,100 @@ pub async fn api_docs(State(_state): State<AppState>) -> Html<String> {
   "prompt": "一只可爱的小猫",
   "size": "1:1",
   "model": "qwen3-max",
+  "n": 2,
+  "response_format": "url",
   "download": true,
   "thread_id": "optional-for-continuous"
 }</div>
                 <h4>响应</h4>
                 <div class="code-block">{
+  "created": 1700000000,
+  "data": [
+    {"url": "https://cdn.qwenlm.ai/...", "media_url": "/v1/media/3f2a...e9.png"},
+    {"url": "https://cdn.qwenlm.ai/...", "media_url": "/v1/media/7b1c...04.png"}
+  ],
   "image_url": "https://cdn.qwenlm.ai/...",
   "prompt": "一只可爱的小猫",
   "chat_id": "chat-id",
   "response_id": "response-id",
-  "local_path": "./generated/generated_image_xxx.png"
+  "media_url": "/v1/media/3f2a...e9.png"
 }</div>
-                <div class="note success">设置 download=true 会自动下载到 ./generated/ 目录</div>
+                <div class="note">n 取值 1-4（默认 1）。response_format 为 "b64_json" 时 data 中返回 b64_json 而不是 url；image_url 始终为第一张图片</div>
+                <div class="note success">设置 download=true 会把图片保存到服务器（--media-dir），通过 media_url 访问</div>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method post">POST</span><span class="path">/v1/images/edits</span></div>
+                <p>使用Qwen编辑图片（与 OpenAI 图片编辑接口的 multipart 格式兼容）</p>
+                <h4>请求 (multipart/form-data)</h4>
+                <div class="code-block">image: &lt;原图文件&gt;
+prompt: 把背景换成海边
+size: 1:1            (可选)
+model: qwen3-max     (可选)
+response_format: url (可选，url 或 b64_json)
+download: true       (可选)</div>
+                <h4>响应</h4>
+                <div class="code-block">{
+  "created": 1700000000,
+  "data": [{"url": "https://cdn.qwenlm.ai/..."}],
+  "image_url": "https://cdn.qwenlm.ai/...",
+  "prompt": "把背景换成海边",
+  "chat_id": "chat-id",
+  "response_id": "response-id",
+  "media_url": "/v1/media/9d4e...a1.png"
+}</div>
+                <div class="note">不支持 mask 参数</div>
             </div>
             
             <div class="endpoint">
                 <div><span class="method post">POST</span><span class="path">/v1/videos/generate</span></div>
-                <p>使用Qwen生成视频（需要1-3分钟）</p>
+                <p>提交Qwen视频生成任务，立即返回任务（HTTP 202），生成在后台进行（需要1-3分钟）</p>
                 <h4>请求体</h4>
                 <div class="code-block">{
   "prompt": "一只小猫在草地上玩耍",
   "size": "16:9",
   "model": "qwen3-max",
+  "image_file_id": "optional-uploaded-image-id",
   "download": true
 }</div>
                 <h4>响应</h4>
                 <div class="code-block">{
-  "video_url": "https://cdn.qwenlm.ai/...",
+  "id": "video-xxx",
+  "status": "queued",
   "prompt": "一只小猫在草地上玩耍",
-  "chat_id": "chat-id",
-  "response_id": "response-id",
-  "local_path": "./generated/generated_video_xxx.mp4"
+  "model": "qwen3-max",
+  "size": "16:9",
+  "task_id": null,
+  "remaining_secs": null,
+  "message": null,
+  "video_url": null,
+  "media_url": null,
+  "error": null,
+  "created_at": 1700000000,
+  "updated_at": 1700000000
 }</div>
-                <div class="note">视频生成耗时较长，请耐心等待。支持的尺寸: 1:1, 16:9, 9:16</div>
+                <div class="note">image_file_id 为 /v1/files 上传的图片ID，作为视频首帧（图生视频）。任务在客户端断开后继续执行。支持的尺寸: 1:1, 16:9, 9:16</div>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method get">GET</span><span class="path">/v1/videos/{job_id}</span></div>
+                <p>查询视频任务：status 为 queued、running、succeeded、failed 或 cancelled；运行中返回 remaining_secs（预计剩余秒数）和 message，完成后返回 video_url 和 media_url（download=true 时），失败时返回 error</p>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method get">GET</span><span class="path">/v1/videos</span></div>
+                <p>列出视频任务（最新的在前），已完成的任务保留 24 小时</p>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method delete">DELETE</span><span class="path">/v1/videos/{job_id}</span></div>
+                <p>停止轮询并将任务标记为 cancelled</p>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method get">GET</span><span class="path">/v1/media/{media_id}</span></div>
+                <p>下载 download=true 时保存到服务器的图片或视频（media_url 即此地址）。支持 Range 请求（返回 206），Content-Type 按扩展名识别</p>
+                <div class="note">media_id 为文件内容的 SHA-256，无需 API 密钥即可访问，方便直接用于 &lt;img&gt; / &lt;video&gt;。超过保留期（--media-retention-hours，默认 24 小时）的文件会被删除</div>
             </div>
             
             <h3>高级功能 (Qwen)</h3>
@@ -369,16 +478,30 @@ pub async fn api_docs(State(_state): State<AppState>) -> Html<String> {
   "thread_id": "thread-123",
   "model": "qwen3-max",
   "file_ids": ["file-id-1", "file-id-2"],
-  "instructions": "search",
-  "instructions": "thinking"
+  "enable_search": true,
+  "enable_thinking": true,
+  "thinking_budget": 8192
 }</div>
                 <div class="note">
-                    <strong>instructions 参数:</strong>
+                    <strong>chat_type 参数:</strong>
                     <ul style="margin-left: 20px; margin-top: 5px;">
+                        <li><strong>t2t</strong>: 普通对话（默认）</li>
                         <li><strong>search</strong>: 启用联网搜索功能</li>
-                        <li><strong>thinking</strong>: 启用深度思考模式</li>
+                        <li><strong>deep_research</strong>: 深度研究，流式返回时通过 phase 事件报告各阶段进度</li>
+                        <li><strong>image_edit</strong>: 图片编辑，需通过 file_ids 传入原图</li>
+                        <li><strong>web_dev</strong> / <strong>artifacts</strong>: 生成网页或代码产物</li>
+                        <li><strong>travel</strong>: 旅行规划</li>
+                        <li><strong>t2i</strong> / <strong>t2v</strong>: 文生图 / 文生视频（t2v 不支持流式）</li>
+                    </ul>
+                    <strong>其他参数（可任意组合）:</strong>
+                    <ul style="margin-left: 20px; margin-top: 5px;">
+                        <li><strong>enable_search</strong>: 启用联网搜索，等同于 chat_type 为 search，不能与其他 chat_type 同时使用</li>
+                        <li><strong>enable_thinking</strong>: 启用深度思考模式</li>
+                        <li><strong>thinking_budget</strong>: 思考预算（token 数），超出模型上限时返回 400，仅 Qwen 支持</li>
                         <li><strong>file_ids</strong>: 传递已上传的文件ID列表进行多模态分析</li>
                     </ul>
+                    <code>instructions</code> 不再用于开启搜索或思考。
+                    DeepSeek 模型同样支持 search 和 thinking，<code>deepseek-reasoner</code> 始终启用深度思考。
                 </div>
             </div>
             
@@ -398,10 +521,15 @@ pub async fn api_docs(State(_state): State<AppState>) -> Html<String> {
             
             <div class="endpoint">
                 <div><span class="method post">POST</span><span class="path">/v1/config/deepseek</span></div>
-                <p>配置DeepSeek Token</p>
+                <p>配置DeepSeek Token，之后可使用 <code>deepseek-chat</code> 和 <code>deepseek-reasoner</code> 模型</p>
                 <h4>请求体</h4>
                 <div class="code-block">{
   "token": "your_deepseek_token"
+}</div>
+                <h4>响应</h4>
+                <div class="code-block">{
+  "status": "success",
+  "message": "DeepSeek token configured"
 }</div>
             </div>
             
@@ -415,7 +543,49 @@ pub async fn api_docs(State(_state): State<AppState>) -> Html<String> {
                 <h4>响应</h4>
                 <div class="code-block">{
   "status": "success",
-  "message": "Qwen token configured"
+  "message": "Qwen token added to the pool",
+  "token": { "id": "tok_1a2b3c4d5e6f", "token": "eyJh...x9Qk", "status": "healthy", ... }
+}</div>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method get">GET</span><span class="path">/v1/config/qwen/tokens</span></div>
+                <p>查看 Qwen token 池中每个 token 的状态（token 已脱敏）。认证失败的 token 冷却 30 分钟，限流的 token 冷却 60 秒，期间请求会自动切换到下一个健康的 token</p>
+                <h4>响应</h4>
+                <div class="code-block">{
+  "object": "list",
+  "data": [
+    {
+      "id": "tok_1a2b3c4d5e6f",
+      "token": "eyJh...x9Qk",
+      "status": "cooling_down",
+      "successes": 42,
+      "failures": 1,
+      "consecutive_failures": 1,
+      "last_error": "API Error: Chat completion failed (429 Too Many Requests): ...",
+      "last_used_at": 1699999999,
+      "cooldown_remaining_secs": 37
+    }
+  ]
+}</div>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method post">POST</span><span class="path">/v1/config/qwen/tokens</span></div>
+                <p>向 token 池添加一个 Qwen token，返回该 token 的状态</p>
+                <h4>请求体</h4>
+                <div class="code-block">{
+  "token": "your_qwen_token"
+}</div>
+            </div>
+            
+            <div class="endpoint">
+                <div><span class="method delete">DELETE</span><span class="path">/v1/config/qwen/tokens/{token_id}</span></div>
+                <p>按 id 从 token 池移除 Qwen token</p>
+                <h4>响应</h4>
+                <div class="code-block">{
+  "id": "tok_1a2b3c4d5e6f",
+  "deleted": true
 }</div>
             </div>
             
@@ -524,22 +694,44 @@ curl -X POST http://localhost:6969/v1/responses \
                     <td>400 Bad Request</td>
                     <td>请求参数错误</td>
                 </tr>
+                <tr>
+                    <td>401 Unauthorized</td>
+                    <td>API 密钥无效，或 Qwen Token 被上游拒绝（失效或过期）</td>
+                </tr>
                 <tr>
                     <td>404 Not Found</td>
-                    <td>资源不存在</td>
+                    <td>资源不存在，或上游找不到指定的模型、会话或任务</td>
+                </tr>
+                <tr>
+                    <td>429 Too Many Requests</td>
+                    <td>上游限流，上游给出等待时间时附带 <code>Retry-After</code> 头</td>
                 </tr>
                 <tr>
                     <td>500 Internal Server Error</td>
                     <td>服务器错误</td>
                 </tr>
+                <tr>
+                    <td>502 Bad Gateway</td>
+                    <td>上游不可用或返回了无法识别的错误</td>
+                </tr>
+                <tr>
+                    <td>503 Service Unavailable</td>
+                    <td>所有 Token 都在冷却中</td>
+                </tr>
             </table>
+            <p>内容审核拦截返回 400，<code>code</code> 为 <code>content_filtered</code>。</p>
             
             <h3>错误响应格式</h3>
             <div class="code-block">{
-  "error": "Not found",
-  "status": 404,
-  "details": "Thread with ID 'xyz' not found"
+  "error": {
+    "type": "rate_limit_error",
+    "code": "rate_limited",
+    "message": "Rate Limited: Chat completion failed (429 Too Many Requests): ..."
+  },
+  "status": "error",
+  "detail": "Rate Limited: Chat completion failed (429 Too Many Requests): ..."
 }</div>
+            <p><code>status</code> 和 <code>detail</code> 为兼容旧客户端保留。</p>
         </div>
     </div>
 </body>
@@ -1,48 +1,94 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use reverse_api::QwenError;
+use serde_json::{json, Value};
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct ApiError {
     pub status: StatusCode,
     pub message: String,
+    /// Machine-readable reason, set for upstream errors that were classified
+    pub code: Option<&'static str>,
+    /// Sent back as `Retry-After` when upstream asked us to slow down
+    pub retry_after: Option<Duration>,
 }
 
 impl ApiError {
-    pub fn bad_request(msg: impl Into<String>) -> Self {
+    fn new(status: StatusCode, msg: impl Into<String>) -> Self {
         Self {
-            status: StatusCode::BAD_REQUEST,
+            status,
             message: msg.into(),
+            code: None,
+            retry_after: None,
         }
     }
+
+    pub fn bad_request(msg: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, msg)
+    }
+
     pub fn internal_error(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            message: msg.into(),
-        }
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, msg)
     }
 
     pub fn not_found(msg: impl Into<String>) -> Self {
-        Self {
-            status: StatusCode::NOT_FOUND,
-            message: msg.into(),
+        Self::new(StatusCode::NOT_FOUND, msg)
+    }
+
+    pub fn payload_too_large(msg: impl Into<String>) -> Self {
+        Self::new(StatusCode::PAYLOAD_TOO_LARGE, msg)
+    }
+
+    pub fn service_unavailable(msg: impl Into<String>) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, msg)
+    }
+
+    fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// OpenAI-style error type for the status
+    fn error_type(&self) -> &'static str {
+        match self.status {
+            StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE => "invalid_request_error",
+            StatusCode::UNAUTHORIZED => "authentication_error",
+            StatusCode::NOT_FOUND => "not_found_error",
+            StatusCode::TOO_MANY_REQUESTS => "rate_limit_error",
+            StatusCode::BAD_GATEWAY => "upstream_error",
+            StatusCode::SERVICE_UNAVAILABLE => "service_unavailable",
+            _ => "api_error",
         }
     }
+
+    /// JSON body of the error, also sent as the payload of streamed `error` events.
+    /// `status` and `detail` are kept for clients written against the old format.
+    pub fn body(&self) -> Value {
+        json!({
+            "error": {
+                "type": self.error_type(),
+                "code": self.code,
+                "message": self.message
+            },
+            "status": "error",
+            "detail": self.message
+        })
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        (
-            self.status,
-            Json(json!({
-                "status": "error",
-                "detail": self.message
-            })),
-        )
-            .into_response()
+        let mut response = (self.status, Json(self.body())).into_response();
+        if let Some(retry_after) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, retry_after.as_secs().into());
+        }
+        response
     }
 }
 
@@ -51,3 +97,87 @@ impl From<std::io::Error> for ApiError {
         Self::internal_error(err.to_string())
     }
 }
+
+impl From<QwenError> for ApiError {
+    fn from(err: QwenError) -> Self {
+        let message = err.to_string();
+        match err {
+            QwenError::InvalidRequest(msg) => Self::bad_request(msg).with_code("invalid_request"),
+            QwenError::Unauthorized(_) => {
+                Self::new(StatusCode::UNAUTHORIZED, message).with_code("unauthorized")
+            }
+            QwenError::RateLimited { retry_after, .. } => Self {
+                retry_after,
+                ..Self::new(StatusCode::TOO_MANY_REQUESTS, message).with_code("rate_limited")
+            },
+            QwenError::ContentFiltered(_) => {
+                Self::bad_request(message).with_code("content_filtered")
+            }
+            QwenError::ModelNotFound(_) => Self::not_found(message).with_code("model_not_found"),
+            QwenError::UpstreamUnavailable(_) | QwenError::NetworkError(_) => {
+                Self::new(StatusCode::BAD_GATEWAY, message).with_code("upstream_unavailable")
+            }
+            QwenError::ApiError(_) => {
+                Self::new(StatusCode::BAD_GATEWAY, message).with_code("upstream_error")
+            }
+            _ => Self::internal_error(message),
+        }
+    }
+}
+
+impl From<reverse_api::ProviderError> for ApiError {
+    fn from(err: reverse_api::ProviderError) -> Self {
+        match err {
+            reverse_api::ProviderError::InvalidRequest(msg) => Self::bad_request(msg),
+            reverse_api::ProviderError::UnsupportedModel(_) => Self::bad_request(err.to_string()),
+            reverse_api::ProviderError::Qwen(err) => err.into(),
+            err => Self::internal_error(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qwen_errors_map_to_status_codes() {
+        let cases = [
+            (
+                QwenError::Unauthorized("x".into()),
+                401,
+                "authentication_error",
+            ),
+            (
+                QwenError::ContentFiltered("x".into()),
+                400,
+                "invalid_request_error",
+            ),
+            (QwenError::ModelNotFound("x".into()), 404, "not_found_error"),
+            (
+                QwenError::UpstreamUnavailable("x".into()),
+                502,
+                "upstream_error",
+            ),
+            (
+                QwenError::InvalidRequest("x".into()),
+                400,
+                "invalid_request_error",
+            ),
+        ];
+        for (err, status, error_type) in cases {
+            let err = ApiError::from(err);
+            assert_eq!(err.status.as_u16(), status, "{}", err.message);
+            assert_eq!(err.body()["error"]["type"], error_type);
+        }
+
+        let limited = ApiError::from(QwenError::RateLimited {
+            message: "slow down".into(),
+            retry_after: Some(Duration::from_secs(30)),
+        });
+        assert_eq!(limited.body()["error"]["code"], "rate_limited");
+        let response = limited.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    }
+}
@@ -1,16 +1,36 @@
 use axum::{
     extract::{Multipart, State},
-    response::{IntoResponse, Response as AxumResponse},
-    Json,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response as AxumResponse,
+    },
+    Extension, Json,
 };
-use reverse_api::{Logger, QwenClient};
+use base64::Engine as _;
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use reverse_api::provider::{render_transcript, ChatOptions};
+use reverse_api::qwen::client::modules::file_type::SNIFF_LEN;
+use reverse_api::qwen::client::modules::streaming::StreamingHandler;
+use reverse_api::{
+    ChatMessage, ChatRequest, ChatType, FileKind, Logger, ProviderKind, ProviderRequest,
+    ProviderResponse, QwenClient, QwenError, QwenEvent, QwenEventStream, VideoProgress,
+    VideoRequest,
+};
+use std::convert::Infallible;
 
+use super::auth::ClientKey;
 use super::error::ApiError;
-use super::state::AppState;
+use super::state::{AppState, ThreadState};
 use super::types::*;
+use super::video_jobs::{VideoJob, VideoJobStatus};
+
+/// Upper bound for `n` on `/v1/images/generate`; every image is a separate upstream turn
+const MAX_IMAGES_PER_REQUEST: usize = 4;
 
 pub async fn create_thread(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     Json(payload): Json<CreateThreadRequest>,
 ) -> std::result::Result<AxumResponse, ApiError> {
     let start_time = std::time::Instant::now();
@@ -20,7 +40,12 @@ pub async fn create_thread(
     ));
 
     let (thread_id, thread_state) = state
-        .create_thread(payload.messages, payload.metadata, &payload.model)
+        .create_thread(
+            client_key.owner(),
+            payload.messages,
+            payload.metadata,
+            &payload.model,
+        )
         .await?;
 
     let response = Thread {
@@ -38,10 +63,11 @@ pub async fn create_thread(
 
 pub async fn get_thread(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     axum::extract::Path(params): axum::extract::Path<ThreadPath>,
 ) -> std::result::Result<impl IntoResponse, ApiError> {
     let thread_id = params.thread_id;
-    let thread_state = state.get_thread(&thread_id).await?;
+    let thread_state = state.get_thread(client_key.owner(), &thread_id).await?;
 
     let response = Thread {
         id: thread_id,
@@ -55,8 +81,9 @@ pub async fn get_thread(
 
 pub async fn list_threads(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
 ) -> std::result::Result<AxumResponse, ApiError> {
-    let threads = state.list_threads().await;
+    let threads = state.list_threads(client_key.owner()).await?;
 
     let data: Vec<Thread> = threads
         .into_iter()
@@ -79,10 +106,11 @@ pub async fn list_threads(
 
 pub async fn delete_thread(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     axum::extract::Path(params): axum::extract::Path<ThreadPath>,
 ) -> std::result::Result<AxumResponse, ApiError> {
     let thread_id = params.thread_id;
-    state.delete_thread(&thread_id).await?;
+    state.delete_thread(client_key.owner(), &thread_id).await?;
 
     Ok(Json(serde_json::json!({
         "id": thread_id,
@@ -94,6 +122,7 @@ pub async fn delete_thread(
 
 pub async fn add_message(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     axum::extract::Path(params): axum::extract::Path<ThreadPath>,
     Json(payload): Json<AddMessageRequest>,
 ) -> std::result::Result<AxumResponse, ApiError> {
@@ -103,7 +132,12 @@ pub async fn add_message(
     }
 
     state
-        .add_message_to_thread(&thread_id, payload.role.clone(), payload.content.clone())
+        .add_message_to_thread(
+            client_key.owner(),
+            &thread_id,
+            payload.role.clone(),
+            payload.content.clone(),
+        )
         .await?;
 
     let message_id = uuid::Uuid::new_v4().to_string();
@@ -132,10 +166,11 @@ pub async fn add_message(
 
 pub async fn list_messages(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     axum::extract::Path(params): axum::extract::Path<ThreadPath>,
 ) -> std::result::Result<AxumResponse, ApiError> {
     let thread_id = params.thread_id;
-    let thread_state = state.get_thread(&thread_id).await?;
+    let thread_state = state.get_thread(client_key.owner(), &thread_id).await?;
 
     let data: Vec<Message> = thread_state
         .get_messages()
@@ -174,10 +209,10 @@ pub async fn configure_qwen(
         .as_str()
         .ok_or_else(|| ApiError::bad_request("Missing 'token' field"))?;
 
-    state.set_qwen_token(token.to_string()).await;
+    let info = state.add_qwen_token(token.to_string()).await;
 
     // Try to fetch and cache models
-    match QwenClient::with_token(token.to_string()) {
+    match state.qwen_client(token.to_string()) {
         Ok(client) => match client.get_models().await {
             Ok(models) => {
                 Logger::info(&format!("✅ Fetched {} Qwen models", models.len()));
@@ -194,13 +229,81 @@ pub async fn configure_qwen(
 
     Ok(Json(serde_json::json!({
         "status": "success",
-        "message": "Qwen token configured"
+        "message": "Qwen token added to the pool",
+        "token": info
+    }))
+    .into_response())
+}
+
+pub async fn configure_deepseek(
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let token = payload["token"]
+        .as_str()
+        .ok_or_else(|| ApiError::bad_request("Missing 'token' field"))?;
+
+    state
+        .configure_deepseek(token.to_string())
+        .await
+        .map_err(|e| {
+            ApiError::internal_error(format!("Could not create DeepSeek client: {}", e))
+        })?;
+    Logger::info("✅ DeepSeek token configured");
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "message": "DeepSeek token configured"
+    }))
+    .into_response())
+}
+
+pub async fn list_qwen_tokens(
+    State(state): State<AppState>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let tokens = state.list_qwen_tokens().await;
+
+    Ok(Json(serde_json::json!({
+        "object": "list",
+        "data": tokens
+    }))
+    .into_response())
+}
+
+pub async fn add_qwen_token(
+    State(state): State<AppState>,
+    Json(payload): Json<AddQwenTokenRequest>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let token = payload.token.trim();
+    if token.is_empty() {
+        return Err(ApiError::bad_request("'token' must not be empty"));
+    }
+
+    let info = state.add_qwen_token(token.to_string()).await;
+    Logger::info(&format!("Added Qwen token {}", info.id));
+
+    Ok(Json(info).into_response())
+}
+
+pub async fn delete_qwen_token(
+    State(state): State<AppState>,
+    axum::extract::Path(token_id): axum::extract::Path<String>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    if !state.remove_qwen_token(&token_id).await {
+        return Err(ApiError::not_found("Qwen token not found"));
+    }
+    Logger::info(&format!("Removed Qwen token {}", token_id));
+
+    Ok(Json(serde_json::json!({
+        "id": token_id,
+        "deleted": true
     }))
     .into_response())
 }
 
 pub async fn create_response(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     Json(payload): Json<CreateResponseRequest>,
 ) -> std::result::Result<AxumResponse, ApiError> {
     let start_time = std::time::Instant::now();
@@ -208,7 +311,7 @@ pub async fn create_response(
 
     Logger::info(&format!("Creating response for thread: {}", thread_id));
 
-    let mut thread_state = state.get_thread(&thread_id).await?;
+    let thread_state = state.get_thread(client_key.owner(), &thread_id).await?;
 
     let last_user_message = thread_state
         .get_messages()
@@ -226,131 +329,268 @@ pub async fn create_response(
 
     Logger::info(&format!("Using model: {}", model));
 
-    // Determine which client to use based on model name
-    let answer = if model.starts_with("qwen") {
-        Logger::info("Starting Qwen conversation");
+    let provider_kind = ProviderKind::from_model(&model)
+        .ok_or_else(|| ApiError::bad_request(format!("Unsupported model: {}", model)))?;
+
+    let chat_type = match payload.chat_type.unwrap_or_default() {
+        ChatType::TextToText if payload.enable_search => ChatType::Search,
+        chat_type @ (ChatType::TextToText | ChatType::Search) => chat_type,
+        chat_type if payload.enable_search => {
+            return Err(ApiError::bad_request(format!(
+                "enable_search cannot be combined with chat_type {}",
+                chat_type.as_str()
+            )))
+        }
+        chat_type => chat_type,
+    };
+    let use_search = chat_type == ChatType::Search;
+
+    if provider_kind != ProviderKind::Qwen {
+        if !matches!(chat_type, ChatType::TextToText | ChatType::Search) {
+            return Err(ApiError::bad_request(format!(
+                "chat_type {} is only supported for qwen models, not {}",
+                chat_type.as_str(),
+                provider_kind
+            )));
+        }
+        if payload.thinking_budget.is_some() {
+            return Err(ApiError::bad_request(format!(
+                "thinking_budget is only supported for qwen models, not {}",
+                provider_kind
+            )));
+        }
+        if payload.stream || payload.file_ids.as_ref().is_some_and(|ids| !ids.is_empty()) {
+            return Err(ApiError::bad_request(format!(
+                "Streaming and files are only supported for qwen models, not {}",
+                provider_kind
+            )));
+        }
+
+        let request = ProviderRequest::user(&model, message_content).with_options(ChatOptions {
+            enable_search: use_search,
+            enable_thinking: payload.enable_thinking,
+            continuation: thread_state.continuation.clone(),
+        });
+        let result = chat_with_provider(&state, provider_kind, &request).await?;
+
+        let mut thread_state = thread_state;
+        thread_state.continuation = result.continuation;
+        let response = complete_turn(&state, thread_id, thread_state, result.content).await?;
+
+        state
+            .record_request("POST", "/v1/responses", 200, start_time.elapsed(), "")
+            .await;
+        return Ok(Json(response).into_response());
+    }
+
+    Logger::info("Starting Qwen conversation");
+
+    // Build extra_data for continuous conversation
+    let extra_data = if let (Some(chat_id), Some(parent_id)) =
+        (&thread_state.qwen_chat_id, &thread_state.qwen_parent_id)
+    {
+        Some(reverse_api::qwen::models::ExtraData {
+            chat_id: chat_id.clone(),
+            model_id: model.clone(),
+            parent_id: Some(parent_id.clone()),
+        })
+    } else {
+        None
+    };
+
+    let files = match &payload.file_ids {
+        Some(file_ids) if !file_ids.is_empty() => {
+            Logger::info(&format!("Using {} files with Qwen", file_ids.len()));
+
+            // Get uploaded files from state
+            let files = state
+                .get_uploaded_files(client_key.owner(), file_ids)
+                .await?;
+
+            if files.is_empty() {
+                return Err(ApiError::bad_request(
+                    "No valid files found for provided file_ids",
+                ));
+            }
+            files
+        }
+        _ => vec![],
+    };
+
+    if chat_type == ChatType::ImageEdit && files.is_empty() {
+        return Err(ApiError::bad_request(
+            "chat_type image_edit needs the source image in file_ids",
+        ));
+    }
+    if payload.stream && chat_type == ChatType::TextToVideo {
+        return Err(ApiError::bad_request("chat_type t2v cannot be streamed"));
+    }
 
-        // Acquire a client by rotating tokens: get next token and create a client for it
-        let token = state
-            .next_qwen_token()
+    let request = ChatRequest {
+        message: message_content,
+        chat_type,
+        // Files pick a model that can read them
+        model: files.is_empty().then(|| model.clone()),
+        files,
+        enable_thinking: payload.enable_thinking,
+        thinking_budget: payload.thinking_budget,
+        extra_data,
+    };
+
+    if payload.stream {
+        return Ok(stream_response(
+            state,
+            thread_id,
+            thread_state,
+            request,
+            start_time,
+        ));
+    }
+
+    // Each attempt runs on a client for the next healthy token
+    let request = &request;
+
+    Logger::info(&format!(
+        "Calling Qwen in {} mode (thinking: {}, files: {}), model: {}",
+        chat_type.as_str(),
+        request.enable_thinking,
+        request.files.len(),
+        model
+    ));
+    let result = state
+        .run_qwen(move |client| async move { client.send(request.clone()).await })
+        .await?
+        .map_err(ApiError::from)?;
+
+    let response = finish_response(&state, thread_id, thread_state, result).await?;
+
+    state
+        .record_request("POST", "/v1/responses", 200, start_time.elapsed(), "")
+        .await;
+    Ok(Json(response).into_response())
+}
+
+/// Run the Qwen turn in a background task and forward each delta as an SSE event.
+/// Events: `phase`, `thinking`, `web_search`, `image`, `answer`, then `response.completed` (or `error`).
+///
+/// Token failover only covers opening the stream; once deltas flow the turn stays on that token.
+fn stream_response(
+    state: AppState,
+    thread_id: String,
+    thread_state: ThreadState,
+    request: ChatRequest,
+    start_time: std::time::Instant,
+) -> AxumResponse {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+
+    tokio::spawn(async move {
+        let request = &request;
+        let events = state
+            .run_qwen(move |client| async move { client.send_stream(request.clone()).await })
+            .await;
+
+        let mut chat_id = None;
+        let result = match events {
+            Ok(Ok(events)) => StreamingHandler::collect(events, |event| {
+                let event = match event {
+                    QwenEvent::ResponseCreated { chat_id: id, .. } => {
+                        chat_id = Some(id.clone());
+                        return;
+                    }
+                    QwenEvent::PhaseChanged(phase) => Event::default()
+                        .event("phase")
+                        .data(serde_json::json!({ "phase": phase }).to_string()),
+                    QwenEvent::ThinkingDelta(text) => Event::default()
+                        .event("thinking")
+                        .data(serde_json::json!({ "delta": text }).to_string()),
+                    QwenEvent::AnswerDelta(text) => Event::default()
+                        .event("answer")
+                        .data(serde_json::json!({ "delta": text }).to_string()),
+                    QwenEvent::WebSearchResults(results) => Event::default()
+                        .event("web_search")
+                        .data(serde_json::json!({ "results": results }).to_string()),
+                    QwenEvent::ImageGenerated(url) => Event::default()
+                        .event("image")
+                        .data(serde_json::json!({ "url": url }).to_string()),
+                    _ => return,
+                };
+                // The client may have disconnected; keep collecting so the thread is still saved
+                let _ = tx.send(event);
+            })
             .await
-            .ok_or_else(|| ApiError::bad_request("Qwen token not configured. Please configure it via POST /v1/config/qwen"))?;
-        let client = reverse_api::QwenClient::with_token(token).map_err(|e| ApiError::internal_error(format!("Could not create Qwen client: {}", e)))?;
-        // Check for special instructions
-        let use_search = payload
-            .instructions
-            .as_ref()
-            .map(|s| s.contains("search"))
-            .unwrap_or(false);
-        let use_thinking = payload
-            .instructions
-            .as_ref()
-            .map(|s| s.contains("thinking"))
-            .unwrap_or(false);
-
-        // Build extra_data for continuous conversation
-        let extra_data = if let (Some(chat_id), Some(parent_id)) =
-            (&thread_state.qwen_chat_id, &thread_state.qwen_parent_id)
-        {
-            Some(reverse_api::qwen::models::ExtraData {
-                chat_id: chat_id.clone(),
-                model_id: model.clone(),
-                parent_id: Some(parent_id.clone()),
+            .map(|output| reverse_api::QwenResponse {
+                content: output.content,
+                response_id: output.response_id,
+                chat_id,
+                parent_id: None,
+                web_search_results: output.web_search_results,
+                thinking_content: output.thinking_content,
+                images: output.images,
             })
-        } else {
-            None
+            .map_err(ApiError::from),
+            Ok(Err(e)) => Err(ApiError::from(e)),
+            Err(e) => Err(e),
         };
 
-        let result = if let Some(file_ids) = &payload.file_ids {
-            if !file_ids.is_empty() {
-                Logger::info(&format!("Using {} files with Qwen", file_ids.len()));
-
-                // Get uploaded files from state
-                let files = state.get_uploaded_files(file_ids).await;
-
-                if files.is_empty() {
-                    return Err(ApiError::bad_request(
-                        "No valid files found for provided file_ids",
-                    ));
-                }
+        let completed = match result {
+            Ok(result) => finish_response(&state, thread_id, thread_state, result).await,
+            Err(e) => Err(e),
+        };
 
-                // Execute conversation with files
-                client
-                    .start_convo_with_files(
-                        &message_content,
-                        files,
-                        None, // Auto-select model
-                        extra_data.as_ref(),
-                    )
-                    .await
-                    .map_err(|e| {
-                        ApiError::internal_error(format!("Qwen multimodal error: {}", e))
-                    })?
-            } else if use_search {
-                Logger::info("Using Qwen with search");
-                client
-                    .start_convo_with_search(&message_content, Some(&model), extra_data.as_ref())
-                    .await
-                    .map_err(|e| ApiError::internal_error(format!("Qwen search error: {}", e)))?
-            } else if use_thinking {
-                Logger::info("Using Qwen with thinking");
-                client
-                    .start_convo_with_thinking(
-                        &message_content,
-                        Some(&model),
-                        extra_data.as_ref(),
-                        None, // Use default thinking budget
+        let event = match completed {
+            Ok(response) => {
+                state
+                    .record_request("POST", "/v1/responses", 200, start_time.elapsed(), "")
+                    .await;
+                Event::default()
+                    .event("response.completed")
+                    .data(serde_json::to_string(&response).unwrap_or_default())
+            }
+            Err(e) => {
+                Logger::error(&format!("Streaming response failed: {}", e.message));
+                state
+                    .record_request(
+                        "POST",
+                        "/v1/responses",
+                        e.status.as_u16(),
+                        start_time.elapsed(),
+                        "",
                     )
-                    .await
-                    .map_err(|e| ApiError::internal_error(format!("Qwen thinking error: {}", e)))?
-            } else {
-                // Normal text conversation
-                Logger::info(&format!("Calling start_convo with model: {}", model));
-                client
-                    .start_convo(&message_content, Some(&model), extra_data.as_ref())
-                    .await
-                    .map_err(|e| ApiError::internal_error(format!("Qwen error: {}", e)))?
+                    .await;
+                Event::default().event("error").data(e.body().to_string())
             }
-        } else if use_search {
-            Logger::info("Using Qwen with search");
-            client
-                .start_convo_with_search(&message_content, Some(&model), extra_data.as_ref())
-                .await
-                .map_err(|e| ApiError::internal_error(format!("Qwen search error: {}", e)))?
-        } else if use_thinking {
-            Logger::info("Using Qwen with thinking");
-            client
-                .start_convo_with_thinking(
-                    &message_content,
-                    Some(&model),
-                    extra_data.as_ref(),
-                    None, // Use default thinking budget
-                )
-                .await
-                .map_err(|e| ApiError::internal_error(format!("Qwen thinking error: {}", e)))?
-        } else {
-            // Normal text conversation with context
-            Logger::info(&format!("Calling start_convo, model: {}", model));
-            client
-                .start_convo(&message_content, Some(&model), extra_data.as_ref())
-                .await
-                .map_err(|e| ApiError::internal_error(format!("Qwen error: {}", e)))?
         };
+        let _ = tx.send(event);
+    });
 
-        // Update thread state with Qwen session info for continuous conversation
-        if let Some(chat_id) = &result.chat_id {
-            thread_state.qwen_chat_id = Some(chat_id.clone());
-            thread_state.qwen_parent_id = Some(result.response_id.clone());
-        }
+    let stream =
+        tokio_stream::wrappers::UnboundedReceiverStream::new(rx).map(Ok::<Event, Infallible>);
+    Sse::new(stream).into_response()
+}
 
-        result.content
-    } else {
-        return Err(ApiError::bad_request(format!(
-            "Unsupported model: {}. Use 'qwen-*'",
-            model
-        )));
-    };
+/// Store the Qwen session ids on the thread, then complete the turn
+async fn finish_response(
+    state: &AppState,
+    thread_id: String,
+    mut thread_state: ThreadState,
+    result: reverse_api::QwenResponse,
+) -> std::result::Result<Response, ApiError> {
+    // Update thread state with Qwen session info for continuous conversation
+    if let Some(chat_id) = &result.chat_id {
+        thread_state.qwen_chat_id = Some(chat_id.clone());
+        thread_state.qwen_parent_id = Some(result.response_id.clone());
+    }
+
+    complete_turn(state, thread_id, thread_state, result.content).await
+}
 
+/// Store the assistant answer on the thread and build the `Response`
+async fn complete_turn(
+    state: &AppState,
+    thread_id: String,
+    mut thread_state: ThreadState,
+    answer: String,
+) -> std::result::Result<Response, ApiError> {
     thread_state.add_message("assistant".to_string(), answer.clone());
 
     let model = thread_state.model.clone();
@@ -362,7 +602,7 @@ pub async fn create_response(
         .unwrap()
         .as_secs();
 
-    let response = Response {
+    Ok(Response {
         id: response_id,
         object: "thread.response".to_string(),
         created_at,
@@ -370,26 +610,157 @@ pub async fn create_response(
         status: "completed".to_string(),
         model,
         response: Some(answer),
+    })
+}
+
+pub async fn chat_completions(
+    State(state): State<AppState>,
+    Json(payload): Json<ChatCompletionRequest>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let start_time = std::time::Instant::now();
+
+    if payload.messages.is_empty() {
+        return Err(ApiError::bad_request(
+            "'messages' must contain at least one message",
+        ));
+    }
+    // Not forwarded (see `ChatCompletionRequest::temperature`), but still rejected when
+    // no OpenAI client could have sent it
+    if let Some(temperature) = payload.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(ApiError::bad_request(
+                "'temperature' must be between 0 and 2",
+            ));
+        }
+    }
+    if payload.messages.last().map(|m| m.role.as_str()) != Some("user") {
+        return Err(ApiError::bad_request(
+            "The last message must have role 'user'",
+        ));
+    }
+    let provider_kind = ProviderKind::from_model(&payload.model)
+        .ok_or_else(|| ApiError::bad_request(format!("Unsupported model: {}", payload.model)))?;
+    if payload.stream && provider_kind != ProviderKind::Qwen {
+        return Err(ApiError::bad_request(format!(
+            "Streaming is only supported for qwen models, not {}",
+            provider_kind
+        )));
+    }
+
+    let messages: Vec<ChatMessage> = payload
+        .messages
+        .iter()
+        .map(|m| ChatMessage::new(&m.role, &m.content))
+        .collect();
+    let prompt = render_transcript(&messages)?;
+
+    Logger::info(&format!(
+        "Chat completion with {} messages, model: {}",
+        payload.messages.len(),
+        payload.model
+    ));
+
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // Every call starts a fresh Qwen chat: the full history travels in the prompt
+    let prompt_ref = prompt.as_str();
+    let model = payload.model.as_str();
+
+    if payload.stream {
+        let events = state
+            .run_qwen(move |client| async move {
+                client
+                    .stream_convo(prompt_ref, vec![], Some(model), None, false)
+                    .await
+            })
+            .await?
+            .map_err(ApiError::from)?;
+
+        state
+            .record_request(
+                "POST",
+                "/v1/chat/completions",
+                200,
+                start_time.elapsed(),
+                "",
+            )
+            .await;
+
+        return Ok(stream_chat_completion(
+            events,
+            id,
+            created,
+            payload.model,
+            payload.max_tokens,
+        ));
+    }
+
+    let answer = if provider_kind == ProviderKind::Qwen {
+        state
+            .run_qwen(move |client| async move {
+                client.start_convo(prompt_ref, Some(model), None).await
+            })
+            .await?
+            .map_err(ApiError::from)?
+            .content
+    } else {
+        let request = ProviderRequest::new(model, messages);
+        chat_with_provider(&state, provider_kind, &request)
+            .await?
+            .content
     };
 
+    let (content, finish_reason) = match payload.max_tokens {
+        Some(max_tokens) => truncate_to_tokens(&answer, max_tokens),
+        None => (answer, "stop"),
+    };
+
+    let prompt_tokens = estimate_tokens(&prompt);
+    let completion_tokens = estimate_tokens(&content);
+
     state
-        .record_request("POST", "/v1/responses", 200, start_time.elapsed(), "")
+        .record_request(
+            "POST",
+            "/v1/chat/completions",
+            200,
+            start_time.elapsed(),
+            "",
+        )
         .await;
+
+    let response = ChatCompletionResponse {
+        id,
+        object: "chat.completion".to_string(),
+        created,
+        model: payload.model,
+        choices: vec![ChatCompletionChoice {
+            index: 0,
+            message: ChatCompletionMessage {
+                role: "assistant".to_string(),
+                content,
+            },
+            finish_reason: finish_reason.to_string(),
+        }],
+        usage: Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        },
+    };
+
     Ok(Json(response).into_response())
 }
 
 pub async fn upload_file_for_qwen(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     mut multipart: Multipart,
 ) -> std::result::Result<AxumResponse, ApiError> {
-    let token = state
-        .next_qwen_token()
-        .await
-        .ok_or_else(|| ApiError::bad_request("Qwen token not configured. Please configure it via POST /v1/config/qwen"))?;
-    let client = reverse_api::QwenClient::with_token(token)
-        .map_err(|e| ApiError::internal_error(format!("Could not create Qwen client: {}", e)))?;
-
-    let mut file_data: Option<(String, Vec<u8>)> = None;
+    let mut file_data: Option<(String, Bytes)> = None;
 
     while let Some(field) = multipart
         .next_field()
@@ -399,12 +770,9 @@ pub async fn upload_file_for_qwen(
         let field_name = field.name().unwrap_or("file").to_string();
         if field_name == "file" {
             let filename = field.file_name().unwrap_or("unknown").to_string();
-            let data = field
-                .bytes()
-                .await
-                .map_err(|e| ApiError::internal_error(format!("Failed to read file: {}", e)))?;
+            let data = read_upload_field(&state, &filename, field).await?;
 
-            file_data = Some((filename, data.to_vec()));
+            file_data = Some((filename, data));
             break;
         }
     }
@@ -412,22 +780,12 @@ pub async fn upload_file_for_qwen(
     let (filename, data) =
         file_data.ok_or_else(|| ApiError::bad_request("No file provided in multipart request"))?;
 
-    // Save file temporarily
-    let temp_path = format!("/tmp/{}", filename);
-    std::fs::write(&temp_path, &data)
-        .map_err(|e| ApiError::internal_error(format!("Failed to save file: {}", e)))?;
-
-    // Upload to Qwen
-    let file = client
-        .upload_file(&temp_path)
-        .await
-        .map_err(|e| ApiError::internal_error(format!("File upload failed: {}", e)))?;
-
-    // Clean up temp file
-    let _ = std::fs::remove_file(&temp_path);
+    let file = upload_to_qwen(&state, &filename, data).await?;
 
     // Store file in state for later use
-    let file_id = state.store_uploaded_file(file.clone()).await;
+    let file_id = state
+        .store_uploaded_file(client_key.owner(), file.clone())
+        .await?;
 
     let response = FileUploadResponse {
         id: file_id,
@@ -439,22 +797,179 @@ pub async fn upload_file_for_qwen(
     Ok(Json(response).into_response())
 }
 
+/// Read a multipart file field.
+///
+/// The type is sniffed from the first bytes, so unsupported files are rejected with 400
+/// and oversized ones with 413 as soon as they pass the limit for their class. The field
+/// cannot be streamed straight to OSS: a multipart part does not declare its size, which
+/// the STS request needs before the first byte is sent.
+async fn read_upload_field(
+    state: &AppState,
+    filename: &str,
+    mut field: axum::extract::multipart::Field<'_>,
+) -> std::result::Result<Bytes, ApiError> {
+    let mut data = BytesMut::new();
+    let mut kind: Option<FileKind> = None;
+
+    loop {
+        let chunk = field
+            .chunk()
+            .await
+            .map_err(|e| ApiError::bad_request(format!("Failed to read {}: {}", filename, e)))?;
+        let done = chunk.is_none();
+        if let Some(chunk) = chunk {
+            data.extend_from_slice(&chunk);
+        }
+
+        if kind.is_none() && (done || data.len() >= SNIFF_LEN) {
+            let head = &data[..data.len().min(SNIFF_LEN)];
+            kind = Some(QwenClient::file_kind(filename, head).ok_or_else(|| {
+                ApiError::bad_request(format!("Unsupported file type: {}", filename))
+            })?);
+        }
+        if let Some(kind) = &kind {
+            let max = state.upload_limits().max_for(kind.file_class);
+            if data.len() as u64 > max {
+                return Err(ApiError::payload_too_large(format!(
+                    "{} is larger than the {} byte limit for {} files",
+                    filename, max, kind.file_class
+                )));
+            }
+        }
+
+        if done {
+            return Ok(data.freeze());
+        }
+    }
+}
+
+/// Upload bytes received by the server to Qwen.
+///
+/// The data stays in memory rather than being streamed from the request, so the upload
+/// can be retried with another token when the first one is rejected. Each attempt gets
+/// a reference-counted handle to the same buffer, not a copy.
+async fn upload_to_qwen(
+    state: &AppState,
+    filename: &str,
+    data: Bytes,
+) -> std::result::Result<reverse_api::qwen::models::QwenFile, ApiError> {
+    let data = &data;
+    state
+        .run_qwen(move |client| async move { client.upload_bytes(filename, data.clone()).await })
+        .await?
+        .map_err(ApiError::from)
+}
+
+/// Download generated media into the media store and return its `/v1/media/{id}` URL
+async fn save_media(
+    state: &AppState,
+    client: &QwenClient,
+    url: &str,
+    default_extension: &str,
+) -> std::result::Result<String, ApiError> {
+    let bytes = fetch_media(client, url).await?;
+    store_media(state, url, &bytes, default_extension).await
+}
+
+async fn fetch_media(client: &QwenClient, url: &str) -> std::result::Result<Vec<u8>, ApiError> {
+    client
+        .fetch_media(url)
+        .await
+        .map_err(|e| ApiError::internal_error(format!("Failed to download media: {}", e)))
+}
+
+async fn store_media(
+    state: &AppState,
+    url: &str,
+    bytes: &[u8],
+    default_extension: &str,
+) -> std::result::Result<String, ApiError> {
+    let media = state
+        .media()
+        .save(bytes, url, default_extension)
+        .await
+        .map_err(|e| ApiError::internal_error(format!("Failed to store media: {}", e)))?;
+
+    Logger::info(&format!(
+        "Media stored as {} ({}, {} bytes)",
+        media.id, media.mime, media.size
+    ));
+    Ok(media.url())
+}
+
+/// One `data` entry per image URL, storing or inlining the images as requested
+async fn build_image_data(
+    state: &AppState,
+    client: &QwenClient,
+    urls: &[String],
+    format: ImageResponseFormat,
+    download: bool,
+) -> std::result::Result<Vec<ImageData>, ApiError> {
+    let inline = format == ImageResponseFormat::B64Json;
+
+    let mut data = Vec::with_capacity(urls.len());
+    for url in urls {
+        // Each image is fetched at most once, even when it is both stored and inlined
+        let bytes = if download || inline {
+            Some(fetch_media(client, url).await?)
+        } else {
+            None
+        };
+
+        let media_url = match (&bytes, download) {
+            (Some(bytes), true) => Some(store_media(state, url, bytes, "png").await?),
+            _ => None,
+        };
+        let b64_json = bytes
+            .filter(|_| inline)
+            .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes));
+
+        data.push(ImageData {
+            url: b64_json.is_none().then(|| url.clone()),
+            b64_json,
+            media_url,
+        });
+    }
+    Ok(data)
+}
+
+/// Build an image response from a turn whose `images` holds every URL
+fn image_response(
+    result: reverse_api::QwenResponse,
+    prompt: String,
+    data: Vec<ImageData>,
+) -> GenerateImageResponse {
+    GenerateImageResponse {
+        created: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        media_url: data.first().and_then(|d| d.media_url.clone()),
+        data,
+        image_url: result.content,
+        prompt,
+        chat_id: result.chat_id,
+        response_id: result.response_id,
+    }
+}
+
 pub async fn generate_image(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     Json(payload): Json<GenerateImageRequest>,
 ) -> std::result::Result<AxumResponse, ApiError> {
     Logger::info(&format!("Generating image with prompt: {}", payload.prompt));
 
-    let token = state
-        .next_qwen_token()
-        .await
-        .ok_or_else(|| ApiError::bad_request("Qwen token not configured. Please configure it via POST /v1/config/qwen"))?;
-    let client = reverse_api::QwenClient::with_token(token)
-        .map_err(|e| ApiError::internal_error(format!("Could not create Qwen client: {}", e)))?;
+    if !(1..=MAX_IMAGES_PER_REQUEST).contains(&payload.n) {
+        return Err(ApiError::bad_request(format!(
+            "n must be between 1 and {}",
+            MAX_IMAGES_PER_REQUEST
+        )));
+    }
 
     // Get thread state if provided for continuous generation
     let extra_data = if let Some(thread_id) = &payload.thread_id {
-        let thread_state = state.get_thread(thread_id).await?;
+        let thread_state = state.get_thread(client_key.owner(), thread_id).await?;
 
         // Get chat_id and parent_id from thread
         if let Some(last_msg) = thread_state.messages.last() {
@@ -471,71 +986,141 @@ pub async fn generate_image(
         None
     };
 
-    // Generate image
-    let result = client
-        .generate_image(
-            &payload.prompt,
-            payload.size.as_deref(),
-            Some(&payload.model),
-            extra_data.as_ref(),
-        )
-        .await
-        .map_err(|e| ApiError::internal_error(format!("Image generation failed: {}", e)))?;
+    // Generate image, keeping the client that succeeded for the download below
+    let request = &payload;
+    let extra_data = extra_data.as_ref();
+    let (client, result) = state
+        .run_qwen(move |client| async move {
+            let result = client
+                .generate_images(
+                    &request.prompt,
+                    request.n,
+                    request.size.as_deref(),
+                    Some(&request.model),
+                    extra_data,
+                )
+                .await?;
+            Ok((client, result))
+        })
+        .await?
+        .map_err(ApiError::from)?;
 
-    Logger::info(&format!("Image generated: {}", result.content));
+    Logger::info(&format!(
+        "{} image(s) generated: {}",
+        result.images.len(),
+        result.images.join(", ")
+    ));
 
-    // Download image if requested
-    let local_path = if payload.download {
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let filename = format!("generated_image_{}.png", timestamp);
-        let filepath = format!("./generated/{}", filename);
+    let data = build_image_data(
+        &state,
+        &client,
+        &result.images,
+        payload.response_format,
+        payload.download,
+    )
+    .await?;
+
+    Ok(Json(image_response(result, payload.prompt, data)).into_response())
+}
 
-        // Create directory if it doesn't exist
-        std::fs::create_dir_all("./generated")
-            .map_err(|e| ApiError::internal_error(format!("Failed to create directory: {}", e)))?;
+/// OpenAI-style image edit: multipart `image`, `prompt`, and optional `size`, `model`,
+/// `response_format`, `download`
+pub async fn edit_image(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let mut image: Option<(String, Bytes)> = None;
+    let mut prompt = None;
+    let mut size = None;
+    let mut model = "qwen3-max".to_string();
+    let mut download = false;
+    let mut response_format = ImageResponseFormat::Url;
 
-        Logger::info(&format!("Downloading image to: {}", filepath));
-        client
-            .download_media(&result.content, &filepath)
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::bad_request(format!("Multipart error: {}", e)))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        if name == "image" || name == "image[]" {
+            let filename = field.file_name().unwrap_or("image.png").to_string();
+            let data = read_upload_field(&state, &filename, field).await?;
+            image = Some((filename, data));
+            continue;
+        }
+
+        let value = field
+            .text()
             .await
-            .map_err(|e| ApiError::internal_error(format!("Failed to download image: {}", e)))?;
+            .map_err(|e| ApiError::bad_request(format!("Failed to read {}: {}", name, e)))?;
+        match name.as_str() {
+            "prompt" => prompt = Some(value),
+            "size" => size = Some(value),
+            "model" => model = value,
+            "download" => download = value == "true",
+            "response_format" => {
+                response_format = match value.as_str() {
+                    "url" => ImageResponseFormat::Url,
+                    "b64_json" => ImageResponseFormat::B64Json,
+                    other => {
+                        return Err(ApiError::bad_request(format!(
+                            "Unknown response_format: {}",
+                            other
+                        )))
+                    }
+                }
+            }
+            "mask" => return Err(ApiError::bad_request("mask is not supported")),
+            _ => {}
+        }
+    }
 
-        Logger::info(&format!("Image saved to: {}", filepath));
-        Some(filepath)
-    } else {
-        None
-    };
+    let (filename, data) =
+        image.ok_or_else(|| ApiError::bad_request("No image provided in multipart request"))?;
+    let prompt = prompt
+        .filter(|p| !p.trim().is_empty())
+        .ok_or_else(|| ApiError::bad_request("prompt is required"))?;
 
-    let response = GenerateImageResponse {
-        image_url: result.content,
-        prompt: payload.prompt,
-        chat_id: result.chat_id,
-        response_id: result.response_id,
-        local_path,
-    };
+    Logger::info(&format!(
+        "Editing image {} with prompt: {}",
+        filename, prompt
+    ));
 
-    Ok(Json(response).into_response())
+    let source = upload_to_qwen(&state, &filename, data).await?;
+
+    let prompt_ref = prompt.as_str();
+    let size_ref = size.as_deref();
+    let model_ref = model.as_str();
+    let source = &source;
+    let (client, result) = state
+        .run_qwen(move |client| async move {
+            let result = client
+                .edit_image(prompt_ref, source.clone(), size_ref, Some(model_ref))
+                .await?;
+            Ok((client, result))
+        })
+        .await?
+        .map_err(ApiError::from)?;
+
+    Logger::info(&format!("Image edited: {}", result.content));
+
+    // An edit yields one image
+    let urls = [result.content.clone()];
+    let data = build_image_data(&state, &client, &urls, response_format, download).await?;
+
+    Ok(Json(image_response(result, prompt, data)).into_response())
 }
 
 pub async fn generate_video(
     State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
     Json(payload): Json<GenerateVideoRequest>,
 ) -> std::result::Result<AxumResponse, ApiError> {
     Logger::info(&format!("Generating video with prompt: {}", payload.prompt));
 
-    let token = state
-        .next_qwen_token()
-        .await
-        .ok_or_else(|| ApiError::bad_request("Qwen token not configured. Please configure it via POST /v1/config/qwen"))?;
-    let client = reverse_api::QwenClient::with_token(token)
-        .map_err(|e| ApiError::internal_error(format!("Could not create Qwen client: {}", e)))?;
-
     // Get thread state if provided for continuous generation
     let extra_data = if let Some(thread_id) = &payload.thread_id {
-        let thread_state = state.get_thread(thread_id).await?;
+        let thread_state = state.get_thread(client_key.owner(), thread_id).await?;
 
         // Get chat_id and parent_id from thread
         if let Some(last_msg) = thread_state.messages.last() {
@@ -551,57 +1136,307 @@ pub async fn generate_video(
         None
     };
 
-    // Generate video with progress logging
-    Logger::info("Starting video generation (this may take 1-3 minutes)...");
-    let result = client
-        .generate_video_with_progress(
-            &payload.prompt,
-            payload.size.as_deref(),
-            Some(&payload.model),
-            extra_data.as_ref(),
-            |status, percent| {
-                if percent % 20 == 0 || status == "success" {
-                    Logger::info(&format!("Video generation: {} - {}%", status, percent));
-                }
-            },
-        )
-        .await
-        .map_err(|e| ApiError::internal_error(format!("Video generation failed: {}", e)))?;
+    let first_frame = match &payload.image_file_id {
+        Some(file_id) => Some(
+            state
+                .get_uploaded_file(client_key.owner(), file_id)
+                .await?
+                .ok_or_else(|| ApiError::bad_request(format!("Unknown file: {}", file_id)))?,
+        ),
+        None => None,
+    };
+    if let Some(frame) = &first_frame {
+        if frame.file_class != "vision" {
+            return Err(ApiError::bad_request(format!(
+                "The first frame must be an image, got a {} file",
+                frame.file_class
+            )));
+        }
+    }
 
-    Logger::info(&format!("Video generated: {}", result.content));
+    let job = VideoJob::new(
+        client_key.owner(),
+        payload.prompt.clone(),
+        payload.model.clone(),
+        payload.size.clone(),
+    );
+    let job_id = job.id.clone();
+    state.video_jobs().insert(job.clone()).await;
+
+    // The job keeps running when the client disconnects; it is polled through GET /v1/videos/{job_id}
+    let request = VideoRequest {
+        prompt: payload.prompt.clone(),
+        size: payload.size.clone(),
+        model: Some(payload.model.clone()),
+        first_frame,
+        extra_data,
+    };
+    let handle = tokio::spawn(run_video_job(
+        state.clone(),
+        job_id.clone(),
+        request,
+        payload.download,
+    ));
+    state
+        .video_jobs()
+        .set_handle(&job_id, handle.abort_handle())
+        .await;
 
-    // Download video if requested
-    let local_path = if payload.download {
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let filename = format!("generated_video_{}.mp4", timestamp);
-        let filepath = format!("./generated/{}", filename);
+    Logger::info(&format!("Video job {} queued", job_id));
+    Ok((axum::http::StatusCode::ACCEPTED, Json(job)).into_response())
+}
 
-        // Create directory if it doesn't exist
-        std::fs::create_dir_all("./generated")
-            .map_err(|e| ApiError::internal_error(format!("Failed to create directory: {}", e)))?;
+/// Run a video job to the end; any failure is recorded on the job
+async fn run_video_job(state: AppState, job_id: String, request: VideoRequest, download: bool) {
+    if let Err(message) = drive_video_job(&state, &job_id, &request, download).await {
+        Logger::error(&format!("Video job {} failed: {}", job_id, message));
+        state
+            .video_jobs()
+            .update(&job_id, |job| {
+                job.status = VideoJobStatus::Failed;
+                job.remaining_secs = None;
+                job.error = Some(message);
+            })
+            .await;
+    }
+}
 
-        Logger::info(&format!("Downloading video to: {}", filepath));
-        client
-            .download_media(&result.content, &filepath)
-            .await
-            .map_err(|e| ApiError::internal_error(format!("Failed to download video: {}", e)))?;
+/// Submit the video task, poll it to completion and record every step on the job
+async fn drive_video_job(
+    state: &AppState,
+    job_id: &str,
+    request: &VideoRequest,
+    download: bool,
+) -> std::result::Result<(), String> {
+    let jobs = state.video_jobs();
+
+    // Keep the client whose token owns the task; status requests must use the same token
+    let (client, task) = state
+        .run_qwen(move |client| async move {
+            let task = client.start_video_request(request).await?;
+            Ok((client, task))
+        })
+        .await
+        .map_err(|e| e.message)?
+        .map_err(|e| format!("Video generation failed: {}", e))?;
 
-        Logger::info(&format!("Video saved to: {}", filepath));
-        Some(filepath)
+    Logger::info(&format!(
+        "Video job {} running as task {}",
+        job_id, task.task_id
+    ));
+    jobs.update(job_id, |job| {
+        job.status = VideoJobStatus::Running;
+        job.task_id = Some(task.task_id.clone());
+        job.chat_id = Some(task.chat_id.clone());
+        job.response_id = Some(task.response_id.clone());
+    })
+    .await;
+
+    // Progress arrives through a sync callback; forward it to a task that can update the job
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel::<VideoProgress>();
+    let wait = client.wait_for_video(&task.task_id, move |progress| {
+        let _ = progress_tx.send(progress.clone());
+    });
+    let record = async {
+        while let Some(progress) = progress_rx.recv().await {
+            jobs.update(job_id, |job| {
+                job.remaining_secs = progress.remaining.map(|d| d.as_secs());
+                job.message = Some(progress.message).filter(|m| !m.is_empty());
+            })
+            .await;
+        }
+    };
+    let (video_url, ()) = tokio::join!(wait, record);
+    let video_url = video_url.map_err(|e| e.to_string())?;
+
+    let media_url = if download {
+        Some(
+            save_media(state, &client, &video_url, "mp4")
+                .await
+                .map_err(|e| e.message)?,
+        )
     } else {
         None
     };
 
-    let response = GenerateVideoResponse {
-        video_url: result.content,
-        prompt: payload.prompt,
-        chat_id: result.chat_id,
-        response_id: result.response_id,
-        local_path,
+    Logger::success(&format!("Video job {} completed: {}", job_id, video_url));
+    jobs.update(job_id, |job| {
+        job.status = VideoJobStatus::Succeeded;
+        job.remaining_secs = None;
+        job.video_url = Some(video_url);
+        job.media_url = media_url;
+    })
+    .await;
+
+    Ok(())
+}
+
+pub async fn list_video_jobs(
+    State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let jobs = state.video_jobs().list(client_key.owner()).await;
+
+    Ok(Json(serde_json::json!({
+        "object": "list",
+        "data": jobs
+    }))
+    .into_response())
+}
+
+pub async fn get_video_job(
+    State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let job = state
+        .video_jobs()
+        .get(client_key.owner(), &job_id)
+        .await
+        .ok_or_else(|| ApiError::not_found("Video job not found"))?;
+
+    Ok(Json(job).into_response())
+}
+
+/// Stop polling a job; the upstream task itself cannot be cancelled
+pub async fn cancel_video_job(
+    State(state): State<AppState>,
+    Extension(client_key): Extension<ClientKey>,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> std::result::Result<AxumResponse, ApiError> {
+    let job = state
+        .video_jobs()
+        .cancel(client_key.owner(), &job_id)
+        .await
+        .ok_or_else(|| ApiError::not_found("Video job not found"))?;
+    Logger::info(&format!("Video job {} is {:?}", job_id, job.status));
+
+    Ok(Json(job).into_response())
+}
+
+/// Stream a stored image or video back; `Range` requests get a 206 partial response
+pub async fn get_media(
+    State(state): State<AppState>,
+    axum::extract::Path(media_id): axum::extract::Path<String>,
+    headers: axum::http::HeaderMap,
+) -> std::result::Result<AxumResponse, ApiError> {
+    state.media().serve(&media_id, &headers).await
+}
+
+/// Run a turn on the non-Qwen provider registered for `kind`
+async fn chat_with_provider(
+    state: &AppState,
+    kind: ProviderKind,
+    request: &ProviderRequest,
+) -> std::result::Result<ProviderResponse, ApiError> {
+    let provider = state.provider(kind).await.ok_or_else(|| {
+        ApiError::bad_request(format!("No {} provider is configured on this server", kind))
+    })?;
+
+    Logger::info(&format!("Dispatching {} to {}", request.model, kind));
+    Ok(provider.chat(request).await?)
+}
+
+/// Forward answer deltas as `chat.completion.chunk` events, ending with `data: [DONE]`
+fn stream_chat_completion(
+    mut events: QwenEventStream,
+    id: String,
+    created: u64,
+    model: String,
+    max_tokens: Option<u32>,
+) -> AxumResponse {
+    let chunk = move |delta: ChatCompletionDelta, finish_reason: Option<&str>| {
+        let chunk = ChatCompletionChunk {
+            id: id.clone(),
+            object: "chat.completion.chunk".to_string(),
+            created,
+            model: model.clone(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta,
+                finish_reason: finish_reason.map(|s| s.to_string()),
+            }],
+        };
+        Event::default().data(serde_json::to_string(&chunk).unwrap_or_default())
     };
 
-    Ok(Json(response).into_response())
+    let stream = async_stream::stream! {
+        yield Ok::<Event, Infallible>(chunk(
+            ChatCompletionDelta {
+                role: Some("assistant".to_string()),
+                content: Some(String::new()),
+            },
+            None,
+        ));
+
+        let mut emitted = String::new();
+        let mut finish_reason = "stop";
+
+        while let Some(event) = events.next().await {
+            let text = match event {
+                Ok(QwenEvent::AnswerDelta(text)) => text,
+                Ok(QwenEvent::Done) => break,
+                Ok(QwenEvent::Error { code, details }) => {
+                    let error = ApiError::from(QwenError::from_event(&code, &details));
+                    yield Ok(Event::default().data(error.body().to_string()));
+                    return;
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    yield Ok(Event::default().data(ApiError::from(e).body().to_string()));
+                    return;
+                }
+            };
+
+            let full = format!("{}{}", emitted, text);
+            let (kept, reason) = match max_tokens {
+                Some(max_tokens) => truncate_to_tokens(&full, max_tokens),
+                None => (full, "stop"),
+            };
+
+            let piece = kept[emitted.len()..].to_string();
+            if !piece.is_empty() {
+                yield Ok(chunk(
+                    ChatCompletionDelta {
+                        role: None,
+                        content: Some(piece),
+                    },
+                    None,
+                ));
+            }
+            emitted = kept;
+
+            if reason == "length" {
+                finish_reason = reason;
+                break;
+            }
+        }
+
+        yield Ok(chunk(ChatCompletionDelta::default(), Some(finish_reason)));
+        yield Ok(Event::default().data("[DONE]"));
+    };
+
+    Sse::new(stream).into_response()
+}
+
+/// Rough token count: ~4 ASCII characters per token, one token per other character.
+fn estimate_tokens(text: &str) -> u32 {
+    let ascii = text.chars().filter(|c| c.is_ascii()).count() as u32;
+    let other = text.chars().count() as u32 - ascii;
+    ascii.div_ceil(4) + other
+}
+
+/// Cut `text` down to roughly `max_tokens`, returning the OpenAI finish reason.
+fn truncate_to_tokens(text: &str, max_tokens: u32) -> (String, &'static str) {
+    let budget = max_tokens as usize * 4;
+    let mut used = 0;
+
+    for (idx, c) in text.char_indices() {
+        used += if c.is_ascii() { 1 } else { 4 };
+        if used > budget {
+            return (text[..idx].to_string(), "length");
+        }
+    }
+
+    (text.to_string(), "stop")
 }
@@ -0,0 +1,327 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use super::error::ApiError;
+
+/// Bytes read from disk per chunk when streaming a file back
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Generated images and videos, kept on disk and served through `GET /v1/media/{id}`.
+///
+/// Files are named after the SHA-256 of their content, so saving the same media twice
+/// yields the same id and ids cannot be guessed.
+pub struct MediaStore {
+    root: PathBuf,
+    /// Files older than this are removed; `None` keeps them forever
+    retention: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredMedia {
+    pub id: String,
+    pub mime: String,
+    pub size: u64,
+}
+
+impl StoredMedia {
+    /// Path of the media on this server
+    pub fn url(&self) -> String {
+        format!("/v1/media/{}", self.id)
+    }
+}
+
+impl MediaStore {
+    pub async fn open(
+        root: impl AsRef<Path>,
+        retention: Option<Duration>,
+    ) -> std::io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        tokio::fs::create_dir_all(&root).await?;
+        Ok(Self { root, retention })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Store `bytes` downloaded from `source_url`.
+    ///
+    /// The extension comes from the URL path when `mime_guess` knows it, otherwise
+    /// `default_extension` is used.
+    pub async fn save(
+        &self,
+        bytes: &[u8],
+        source_url: &str,
+        default_extension: &str,
+    ) -> std::io::Result<StoredMedia> {
+        let extension = extension_for(source_url, default_extension);
+        let id = format!("{}.{}", hex::encode(Sha256::digest(bytes)), extension);
+        let path = self.root.join(&id);
+
+        // Same content, same file: only the first save writes it. Concurrent saves of the
+        // same bytes each write their own temp file; the last rename wins harmlessly.
+        if tokio::fs::metadata(&path).await.is_err() {
+            let tmp_path = self
+                .root
+                .join(format!("{}.{}.tmp", id, uuid::Uuid::new_v4().simple()));
+            tokio::fs::write(&tmp_path, bytes).await?;
+            tokio::fs::rename(&tmp_path, &path).await?;
+        }
+
+        Ok(StoredMedia {
+            mime: mime_for(&id),
+            size: bytes.len() as u64,
+            id,
+        })
+    }
+
+    /// Path of a stored file, or `None` for unknown ids and ids that could escape the root
+    pub async fn find(&self, id: &str) -> Option<PathBuf> {
+        let (hash, extension) = id.split_once('.')?;
+        let valid = hash.len() == 64
+            && hash.chars().all(|c| c.is_ascii_hexdigit())
+            && !extension.is_empty()
+            && extension.chars().all(|c| c.is_ascii_alphanumeric());
+        if !valid {
+            return None;
+        }
+
+        let path = self.root.join(id);
+        tokio::fs::metadata(&path)
+            .await
+            .ok()
+            .filter(|m| m.is_file())
+            .map(|_| path)
+    }
+
+    /// Remove files older than the retention period
+    pub async fn prune(&self) -> std::io::Result<usize> {
+        let Some(retention) = self.retention else {
+            return Ok(0);
+        };
+
+        let now = SystemTime::now();
+        let mut removed = 0;
+        let mut entries = tokio::fs::read_dir(&self.root).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            // A concurrent save may have renamed its temp file in the meantime
+            let metadata = match entry.metadata().await {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age > retention {
+                match tokio::fs::remove_file(entry.path()).await {
+                    Ok(()) => removed += 1,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Serve a stored file, honouring a single `Range: bytes=...` request header
+    pub async fn serve(&self, id: &str, headers: &HeaderMap) -> Result<Response, ApiError> {
+        let path = self
+            .find(id)
+            .await
+            .ok_or_else(|| ApiError::not_found("Media not found"))?;
+
+        let mut file = tokio::fs::File::open(&path).await?;
+        let total = file.metadata().await?.len();
+
+        let range = headers
+            .get(header::RANGE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| parse_range(value, total));
+
+        let (status, start, len) = match range {
+            None => (StatusCode::OK, 0, total),
+            Some(Some((start, end))) => (StatusCode::PARTIAL_CONTENT, start, end - start + 1),
+            Some(None) => {
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, format!("bytes */{}", total))],
+                )
+                    .into_response());
+            }
+        };
+
+        file.seek(std::io::SeekFrom::Start(start)).await?;
+        let mut response = Response::new(Body::from_stream(read_chunks(file, len)));
+        *response.status_mut() = status;
+        let response_headers = response.headers_mut();
+        response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+        if let Ok(mime) = HeaderValue::from_str(&mime_for(id)) {
+            response_headers.insert(header::CONTENT_TYPE, mime);
+        }
+        if status == StatusCode::PARTIAL_CONTENT {
+            let content_range = format!("bytes {}-{}/{}", start, start + len - 1, total);
+            if let Ok(value) = HeaderValue::from_str(&content_range) {
+                response_headers.insert(header::CONTENT_RANGE, value);
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+/// Stream up to `len` bytes of `file` from its current position
+fn read_chunks(
+    mut file: tokio::fs::File,
+    len: u64,
+) -> impl futures::Stream<Item = std::io::Result<Bytes>> {
+    async_stream::try_stream! {
+        let mut remaining = len;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        while remaining > 0 {
+            let want = remaining.min(CHUNK_SIZE as u64) as usize;
+            let read = file.read(&mut buffer[..want]).await?;
+            if read == 0 {
+                break;
+            }
+            remaining -= read as u64;
+            yield Bytes::copy_from_slice(&buffer[..read]);
+        }
+    }
+}
+
+fn extension_for(source_url: &str, default_extension: &str) -> String {
+    let path = url::Url::parse(source_url)
+        .map(|url| url.path().to_string())
+        .unwrap_or_default();
+
+    Path::new(&path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .filter(|e| mime_guess::from_ext(e).first().is_some())
+        .unwrap_or(default_extension)
+        .to_ascii_lowercase()
+}
+
+fn mime_for(id: &str) -> String {
+    mime_guess::from_path(id)
+        .first_or_octet_stream()
+        .essence_str()
+        .to_string()
+}
+
+/// Inclusive byte range for a `Range` header against a file of `total` bytes.
+///
+/// Only single `bytes=` ranges are supported; `None` means the range cannot be satisfied.
+fn parse_range(value: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || total == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        // Suffix range: the last `end` bytes
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return None;
+        }
+        return Some((total.saturating_sub(suffix), total - 1));
+    }
+
+    let start: u64 = start.parse().ok()?;
+    let end = if end.is_empty() {
+        total - 1
+    } else {
+        end.parse::<u64>().ok()?.min(total - 1)
+    };
+
+    (start <= end).then_some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_header_forms() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=900-", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=500-5000", 1000), Some((500, 999)));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+    }
+
+    #[tokio::test]
+    async fn saving_same_content_reuses_the_file() {
+        let dir = std::env::temp_dir().join(format!("api-media-{}", uuid::Uuid::new_v4()));
+        let store = MediaStore::open(&dir, None).await.unwrap();
+
+        let first = store
+            .save(b"video", "https://cdn.example.com/a/clip.mp4?sig=1", "png")
+            .await
+            .unwrap();
+        let second = store
+            .save(b"video", "https://cdn.example.com/b/other.mp4", "png")
+            .await
+            .unwrap();
+
+        assert_eq!(first.id, second.id);
+        assert!(first.id.ends_with(".mp4"));
+        assert_eq!(first.mime, "video/mp4");
+        assert!(store.find(&first.id).await.is_some());
+        assert!(store.find("../secret.mp4").await.is_none());
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_saves_of_the_same_content_succeed() {
+        let dir = std::env::temp_dir().join(format!("api-media-{}", uuid::Uuid::new_v4()));
+        let store = std::sync::Arc::new(
+            MediaStore::open(&dir, Some(Duration::from_secs(3600)))
+                .await
+                .unwrap(),
+        );
+        let bytes = vec![7u8; 4 * 1024 * 1024];
+
+        let saves: Vec<_> = (0..16)
+            .map(|_| {
+                let (store, bytes) = (store.clone(), bytes.clone());
+                tokio::spawn(async move {
+                    store
+                        .save(&bytes, "https://cdn.example.com/a.png", "png")
+                        .await
+                })
+            })
+            .collect();
+
+        let mut ids = Vec::new();
+        for save in saves {
+            ids.push(save.await.unwrap().unwrap().id);
+        }
+        assert!(ids.iter().all(|id| *id == ids[0]));
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(files.len(), 1, "temp files left behind: {:?}", files);
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+}
@@ -1,8 +1,13 @@
+pub mod auth;
 pub mod dashboard;
 pub mod docs;
 pub mod error;
 pub mod handlers;
+pub mod media;
 pub mod server;
 pub mod state;
 pub mod stats;
+pub mod store;
+pub mod token_pool;
 pub mod types;
+pub mod video_jobs;
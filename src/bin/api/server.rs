@@ -1,24 +1,36 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, State},
     http::{header::CONTENT_TYPE, Method},
+    middleware,
     response::IntoResponse,
     routing::{delete, get, post},
     Json, Router,
 };
-use reverse_api::Logger;
+use reverse_api::{Logger, ProviderKind, QwenClientBuilder, UploadLimits};
 use tower_http::cors::{Any, CorsLayer};
 
-use super::{dashboard, docs, handlers, state::AppState};
+use super::{
+    auth::{self, AuthConfig},
+    dashboard, docs, handlers,
+    media::MediaStore,
+    state::{load_deepseek_token, AppState},
+    store::ThreadStore,
+};
 
 pub fn router(state: AppState) -> Router {
+    // Multipart bodies carry a little framing on top of the largest accepted file
+    let upload_body_limit =
+        DefaultBodyLimit::max(state.upload_limits().largest() as usize + 1024 * 1024);
+
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods([Method::POST, Method::GET, Method::OPTIONS, Method::DELETE])
         .allow_headers([CONTENT_TYPE, axum::http::header::AUTHORIZATION]);
 
-    Router::new()
+    let mut protected = Router::new()
         .route("/v1/threads", post(handlers::create_thread))
         .route("/v1/threads", get(handlers::list_threads))
         .route(
@@ -32,22 +44,57 @@ pub fn router(state: AppState) -> Router {
         .route("/v1/threads/{thread_id}", get(handlers::get_thread))
         .route("/v1/threads/{thread_id}", delete(handlers::delete_thread))
         .route("/v1/responses", post(handlers::create_response))
+        .route("/v1/chat/completions", post(handlers::chat_completions))
         .route("/v1/config/qwen", post(handlers::configure_qwen))
-        .route("/v1/files/upload", post(handlers::upload_file_for_qwen))
+        .route("/v1/config/deepseek", post(handlers::configure_deepseek))
+        .route("/v1/config/qwen/tokens", get(handlers::list_qwen_tokens))
+        .route("/v1/config/qwen/tokens", post(handlers::add_qwen_token))
+        .route(
+            "/v1/config/qwen/tokens/{token_id}",
+            delete(handlers::delete_qwen_token),
+        )
+        .route(
+            "/v1/files/upload",
+            post(handlers::upload_file_for_qwen).layer(upload_body_limit),
+        )
         .route("/v1/images/generate", post(handlers::generate_image))
+        .route(
+            "/v1/images/edits",
+            post(handlers::edit_image).layer(upload_body_limit),
+        )
         .route("/v1/videos/generate", post(handlers::generate_video))
-        .route("/health", get(health_check))
-        .route("/v1/models", get(list_models))
+        .route("/v1/videos", get(handlers::list_video_jobs))
+        .route("/v1/videos/{job_id}", get(handlers::get_video_job))
+        .route("/v1/videos/{job_id}", delete(handlers::cancel_video_job))
+        .route("/v1/models", get(list_models));
+
+    // Dashboard and docs stay public: the browser pages cannot send a bearer key
+    let mut public = Router::new()
         .route("/dashboard", get(dashboard::dashboard))
         .route("/dashboard/stats", get(dashboard::dashboard_stats))
         .route("/dashboard/requests", get(dashboard::dashboard_requests))
+        .route("/dashboard/videos", get(dashboard::dashboard_videos))
         .route("/docs", get(docs::api_docs))
-        .with_state(state)
-        .layer(cors)
+        // Media ids are content hashes that cannot be guessed, and <img>/<video> tags
+        // cannot send a bearer key either
+        .route("/v1/media/{media_id}", get(handlers::get_media));
+
+    if state.auth_config().public_health {
+        public = public.route("/health", get(health_check));
+    } else {
+        protected = protected.route("/health", get(health_check));
+    }
+
+    let protected = protected.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        auth::require_api_key,
+    ));
+
+    protected.merge(public).with_state(state).layer(cors)
 }
 
 async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
-    let threads = state.list_threads().await;
+    let threads = state.list_threads(None).await.unwrap_or_default();
 
     Json(serde_json::json!({
         "status": "ok",
@@ -71,15 +118,51 @@ async fn list_models(State(state): State<AppState>) -> impl IntoResponse {
         }
     }
 
+    if state.provider(ProviderKind::DeepSeek).await.is_some() {
+        for id in ["deepseek-chat", "deepseek-reasoner"] {
+            static_models.push(serde_json::json!({
+                "id": id,
+                "object": "model",
+                "name": id,
+                "owned_by": "deepseek"
+            }));
+        }
+    }
+
     Json(serde_json::json!({
         "object": "list",
         "data": static_models
     }))
 }
 
-pub async fn run(host: &str, port: u16) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run(
+    host: &str,
+    port: u16,
+    store: Arc<dyn ThreadStore>,
+    auth: AuthConfig,
+    media: Arc<MediaStore>,
+    upload_limits: UploadLimits,
+    qwen: QwenClientBuilder,
+) -> Result<(), Box<dyn std::error::Error>> {
     let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
-    let state = AppState::new();
+    let state = AppState::new(store, auth, media, upload_limits, qwen);
+    // Grok works anonymously, so it needs no configuration to be served
+    state
+        .register_provider(Arc::new(reverse_api::provider::GrokProvider::new(None)))
+        .await;
+    if let Some(token) = load_deepseek_token() {
+        match state.configure_deepseek(token).await {
+            Ok(()) => Logger::info("✅ DeepSeek token loaded"),
+            Err(e) => Logger::error(&format!("Could not set up DeepSeek client: {}", e)),
+        }
+    }
+    let served: Vec<String> = state
+        .provider_kinds()
+        .await
+        .iter()
+        .map(|kind| format!("{}*", kind))
+        .collect();
+    Logger::info(&format!("Supported Models: {}", served.join(", ")));
     let app = router(state);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -92,8 +175,14 @@ pub async fn run(host: &str, port: u16) -> Result<(), Box<dyn std::error::Error>
     Logger::info("  Threads: POST /v1/threads, GET /v1/threads");
     Logger::info("  Thread: GET/DELETE /v1/threads/:thread_id");
     Logger::info("  Messages: POST/GET /v1/threads/:thread_id/messages");
-    Logger::info("  Response: POST /v1/responses (supports qwen models)");
+    Logger::info("  Response: POST /v1/responses (qwen, deepseek and grok models)");
+    Logger::info("  Chat Completions: POST /v1/chat/completions (OpenAI compatible)");
     Logger::info("  Config Qwen: POST /v1/config/qwen");
+    Logger::info("  Config DeepSeek: POST /v1/config/deepseek");
+    Logger::info(
+        "  Qwen Tokens: GET/POST /v1/config/qwen/tokens, DELETE /v1/config/qwen/tokens/:token_id",
+    );
+    Logger::info("  Media: GET /v1/media/:media_id");
     Logger::info("  Dashboard: GET /dashboard");
     Logger::info("  Dashboard Stats: GET /dashboard/stats");
     Logger::info("  Dashboard Requests: GET /dashboard/requests");
@@ -1,20 +1,38 @@
+use super::auth::AuthConfig;
 use super::error::ApiError;
+use super::media::MediaStore;
 use super::stats::{LiveRequest, RequestStats, StatsCollector};
+use super::store::{StoredFile, ThreadStore};
+use super::token_pool::{FailureKind, TokenInfo, TokenPool};
 use super::types::ThreadMessage;
-use std::collections::HashMap;
+use super::video_jobs::VideoJobs;
+use reverse_api::provider::Continuation;
+use reverse_api::{
+    ChatProvider, Logger, ProviderKind, ProviderRegistry, QwenClient, QwenClientBuilder, QwenError,
+    QwenResult, UploadLimits,
+};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 #[derive(Clone)]
 pub struct AppState {
-    threads: Arc<RwLock<HashMap<String, ThreadState>>>,
+    store: Arc<dyn ThreadStore>,
+    auth: Arc<AuthConfig>,
     stats: StatsCollector,
-    qwen_tokens: Arc<RwLock<Vec<String>>>,
-    qwen_index: Arc<tokio::sync::Mutex<usize>>,
+    qwen_tokens: Arc<TokenPool>,
     qwen_models: Arc<RwLock<Option<Vec<reverse_api::qwen::models::Model>>>>,
-    uploaded_files: Arc<RwLock<HashMap<String, reverse_api::qwen::models::QwenFile>>>,
+    /// Non-Qwen backends; Qwen goes through the token pool instead
+    providers: Arc<RwLock<ProviderRegistry>>,
+    video_jobs: Arc<VideoJobs>,
+    media: Arc<MediaStore>,
+    upload_limits: UploadLimits,
+    /// Settings for every Qwen client; clones share one connection pool
+    qwen: QwenClientBuilder,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct ThreadState {
     pub created_at: u64,
     pub metadata: Option<serde_json::Value>,
@@ -22,10 +40,22 @@ pub struct ThreadState {
     pub model: String,
     pub qwen_chat_id: Option<String>,
     pub qwen_parent_id: Option<String>,
+    /// Conversation state for non-Qwen providers
+    #[serde(default)]
+    pub continuation: Option<Continuation>,
+    /// Fingerprint of the API key that created the thread (`None` when auth is disabled)
+    #[serde(default)]
+    pub owner: Option<String>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    pub fn new(
+        store: Arc<dyn ThreadStore>,
+        auth: AuthConfig,
+        media: Arc<MediaStore>,
+        upload_limits: UploadLimits,
+        qwen: QwenClientBuilder,
+    ) -> Self {
         // Load tokens from .qwen_token file if present, each line is a token
         let mut tokens: Vec<String> = vec![];
         if let Ok(contents) = std::fs::read_to_string(".qwen_token") {
@@ -37,31 +67,113 @@ impl AppState {
         }
 
         Self {
-            threads: Arc::new(RwLock::new(HashMap::new())),
+            store,
+            auth: Arc::new(auth),
             stats: StatsCollector::new(),
-            qwen_tokens: Arc::new(RwLock::new(tokens)),
-            qwen_index: Arc::new(tokio::sync::Mutex::new(0)),
+            qwen_tokens: Arc::new(TokenPool::new(tokens)),
             qwen_models: Arc::new(RwLock::new(None)),
-            uploaded_files: Arc::new(RwLock::new(HashMap::new())),
+            providers: Arc::new(RwLock::new(ProviderRegistry::new())),
+            video_jobs: Arc::new(VideoJobs::new()),
+            media,
+            qwen: qwen.upload_limits(upload_limits.clone()),
+            upload_limits,
         }
     }
 
-    pub async fn set_qwen_token(&self, token: String) {
-        let mut tokens = self.qwen_tokens.write().await;
-        tokens.clear();
-        tokens.push(token);
+    pub fn auth_config(&self) -> &AuthConfig {
+        &self.auth
     }
 
-    // Get next token in round-robin fashion. Returns None if no tokens configured
-    pub async fn next_qwen_token(&self) -> Option<String> {
-        let tokens = self.qwen_tokens.read().await;
-        if tokens.is_empty() {
-            return None;
+    pub async fn add_qwen_token(&self, token: String) -> TokenInfo {
+        self.qwen_tokens.add(token).await
+    }
+
+    pub async fn remove_qwen_token(&self, token_id: &str) -> bool {
+        self.qwen_tokens.remove(token_id).await
+    }
+
+    pub async fn list_qwen_tokens(&self) -> Vec<TokenInfo> {
+        self.qwen_tokens.list().await
+    }
+
+    /// Qwen client for `token` using the server's base URL, proxy and connection pool
+    pub fn qwen_client(&self, token: String) -> QwenResult<QwenClient> {
+        self.qwen.clone().token(token).build()
+    }
+
+    /// Run `op` with a client for the next healthy Qwen token.
+    ///
+    /// When the call fails because the token was rejected or rate limited, the token is
+    /// cooled down and `op` is retried with the next healthy one. The outer error means no
+    /// token could be used at all; the inner result is what the last attempt returned.
+    pub async fn run_qwen<T, F, Fut>(&self, mut op: F) -> Result<QwenResult<T>, ApiError>
+    where
+        F: FnMut(QwenClient) -> Fut,
+        Fut: Future<Output = QwenResult<T>>,
+    {
+        let mut tried: Vec<String> = Vec::new();
+        let mut last_error = None;
+
+        while let Some(pooled) = self.qwen_tokens.acquire(&tried).await {
+            let client = self.qwen_client(pooled.token).map_err(|e| {
+                ApiError::internal_error(format!("Could not create Qwen client: {}", e))
+            })?;
+
+            match op(client).await {
+                Ok(value) => {
+                    self.qwen_tokens.report_success(&pooled.id).await;
+                    return Ok(Ok(value));
+                }
+                // The request itself was refused: the caller's fault, not the token's
+                Err(
+                    e @ (QwenError::InvalidRequest(_)
+                    | QwenError::ContentFiltered(_)
+                    | QwenError::ModelNotFound(_)),
+                ) => return Ok(Err(e)),
+                Err(e) => {
+                    let kind = FailureKind::classify(&e);
+                    let retry_after = match &e {
+                        QwenError::RateLimited { retry_after, .. } => *retry_after,
+                        _ => None,
+                    };
+                    self.qwen_tokens
+                        .report_failure(&pooled.id, kind, retry_after, &e.to_string())
+                        .await;
+                    if !kind.is_token_specific() {
+                        return Ok(Err(e));
+                    }
+
+                    Logger::info(&format!(
+                        "⚠️  Qwen token {} failed ({:?}), trying the next one",
+                        pooled.id, kind
+                    ));
+                    tried.push(pooled.id);
+                    last_error = Some(e);
+                }
+            }
         }
-        let mut idx = self.qwen_index.lock().await;
-        let token = tokens.get(*idx % tokens.len()).cloned();
-        *idx = (*idx + 1) % tokens.len();
-        token
+
+        match last_error {
+            Some(e) => Ok(Err(e)),
+            None if self.qwen_tokens.len().await == 0 => Err(ApiError::bad_request(
+                "Qwen token not configured. Please configure it via POST /v1/config/qwen",
+            )),
+            None => Err(ApiError::service_unavailable(
+                "All Qwen tokens are cooling down after upstream errors, try again later",
+            )),
+        }
+    }
+
+    pub fn video_jobs(&self) -> &VideoJobs {
+        &self.video_jobs
+    }
+
+    pub fn media(&self) -> &MediaStore {
+        &self.media
+    }
+
+    pub fn upload_limits(&self) -> &UploadLimits {
+        &self.upload_limits
     }
 
     pub async fn get_qwen_models(&self) -> Option<Vec<reverse_api::qwen::models::Model>> {
@@ -73,30 +185,70 @@ impl AppState {
         *qw_models = Some(models);
     }
 
-    pub async fn store_uploaded_file(&self, file: reverse_api::qwen::models::QwenFile) -> String {
+    /// Build a DeepSeek client for `token` and serve `deepseek-*` models with it
+    pub async fn configure_deepseek(&self, token: String) -> reverse_api::DeepSeekResult<()> {
+        let client = reverse_api::DeepSeekClient::new(token).await?;
+        self.register_provider(Arc::new(client)).await;
+        Ok(())
+    }
+
+    pub async fn register_provider(&self, provider: Arc<dyn ChatProvider>) {
+        self.providers.write().await.register(provider);
+    }
+
+    pub async fn provider(&self, kind: ProviderKind) -> Option<Arc<dyn ChatProvider>> {
+        self.providers.read().await.get(kind).cloned()
+    }
+
+    /// Every provider that can answer right now: Qwen through the token pool, the
+    /// others through the registry
+    pub async fn provider_kinds(&self) -> Vec<ProviderKind> {
+        let mut kinds = vec![ProviderKind::Qwen];
+        kinds.extend(self.providers.read().await.kinds());
+        kinds
+    }
+
+    pub async fn store_uploaded_file(
+        &self,
+        owner: Option<&str>,
+        file: reverse_api::qwen::models::QwenFile,
+    ) -> Result<String, ApiError> {
         let file_id = file.id.clone();
-        let mut files = self.uploaded_files.write().await;
-        files.insert(file_id.clone(), file);
-        file_id
+        self.store
+            .put_file(&StoredFile {
+                owner: owner.map(|o| o.to_string()),
+                file,
+            })
+            .await?;
+        Ok(file_id)
     }
 
     pub async fn get_uploaded_file(
         &self,
+        owner: Option<&str>,
         file_id: &str,
-    ) -> Option<reverse_api::qwen::models::QwenFile> {
-        let files = self.uploaded_files.read().await;
-        files.get(file_id).cloned()
+    ) -> Result<Option<reverse_api::qwen::models::QwenFile>, ApiError> {
+        Ok(self
+            .store
+            .get_file(file_id)
+            .await?
+            .filter(|stored| visible_to(&stored.owner, owner))
+            .map(|stored| stored.file))
     }
 
+    /// Files the caller owns among `file_ids`; unknown or foreign ids are skipped
     pub async fn get_uploaded_files(
         &self,
+        owner: Option<&str>,
         file_ids: &[String],
-    ) -> Vec<reverse_api::qwen::models::QwenFile> {
-        let files = self.uploaded_files.read().await;
-        file_ids
-            .iter()
-            .filter_map(|id| files.get(id).cloned())
-            .collect()
+    ) -> Result<Vec<reverse_api::qwen::models::QwenFile>, ApiError> {
+        let mut files = Vec::with_capacity(file_ids.len());
+        for id in file_ids {
+            if let Some(file) = self.get_uploaded_file(owner, id).await? {
+                files.push(file);
+            }
+        }
+        Ok(files)
     }
 
     pub async fn record_request(
@@ -122,6 +274,7 @@ impl AppState {
 
     pub async fn create_thread(
         &self,
+        owner: Option<&str>,
         messages: Vec<ThreadMessage>,
         metadata: Option<serde_json::Value>,
         model: &str,
@@ -139,70 +292,87 @@ impl AppState {
             model: model.to_string(),
             qwen_chat_id: None,
             qwen_parent_id: None,
+            continuation: None,
+            owner: owner.map(|o| o.to_string()),
         };
 
-        let mut threads = self.threads.write().await;
-        threads.insert(thread_id.clone(), thread_state.clone());
+        self.store.put_thread(&thread_id, &thread_state).await?;
 
         Ok((thread_id, thread_state))
     }
 
-    pub async fn get_thread(&self, thread_id: &str) -> Result<ThreadState, ApiError> {
-        let threads = self.threads.read().await;
-        threads
-            .get(thread_id)
-            .cloned()
+    /// Threads owned by another API key are reported as not found
+    pub async fn get_thread(
+        &self,
+        owner: Option<&str>,
+        thread_id: &str,
+    ) -> Result<ThreadState, ApiError> {
+        self.store
+            .get_thread(thread_id)
+            .await?
+            .filter(|thread| visible_to(&thread.owner, owner))
             .ok_or_else(|| ApiError::not_found("Thread not found"))
     }
 
-    pub async fn list_threads(&self) -> Vec<(String, ThreadState)> {
-        let threads = self.threads.read().await;
-        threads
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect()
+    /// Threads visible to `owner` (all threads when `owner` is `None`), oldest first
+    pub async fn list_threads(
+        &self,
+        owner: Option<&str>,
+    ) -> Result<Vec<(String, ThreadState)>, ApiError> {
+        let mut threads = self.store.list_threads().await?;
+        threads.retain(|(_, thread)| visible_to(&thread.owner, owner));
+        threads.sort_by_key(|(_, t)| t.created_at);
+        Ok(threads)
     }
 
-    pub async fn delete_thread(&self, thread_id: &str) -> Result<(), ApiError> {
-        let mut threads = self.threads.write().await;
-        threads
-            .remove(thread_id)
-            .ok_or_else(|| ApiError::not_found("Thread not found"))?;
+    pub async fn delete_thread(
+        &self,
+        owner: Option<&str>,
+        thread_id: &str,
+    ) -> Result<(), ApiError> {
+        self.get_thread(owner, thread_id).await?;
+        if !self.store.delete_thread(thread_id).await? {
+            return Err(ApiError::not_found("Thread not found"));
+        }
         Ok(())
     }
 
     pub async fn add_message_to_thread(
         &self,
+        owner: Option<&str>,
         thread_id: &str,
         role: String,
         content: String,
     ) -> Result<(), ApiError> {
-        let mut threads = self.threads.write().await;
-        let thread = threads
-            .get_mut(thread_id)
-            .ok_or_else(|| ApiError::not_found("Thread not found"))?;
-
-        let created_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        thread.messages.push(ThreadMessage {
-            role,
-            content,
-            created_at: Some(created_at),
-        });
-
-        Ok(())
+        let mut thread = self.get_thread(owner, thread_id).await?;
+        thread.add_message(role, content);
+        self.store.put_thread(thread_id, &thread).await
     }
 
     pub async fn update_thread(&self, thread_id: &str, state: ThreadState) -> Result<(), ApiError> {
-        let mut threads = self.threads.write().await;
-        threads.insert(thread_id.to_string(), state);
-        Ok(())
+        self.store.put_thread(thread_id, &state).await
     }
 }
 
+/// DeepSeek token from `DEEPSEEK_TOKEN`, falling back to the first line of `.deepseek_token`
+pub fn load_deepseek_token() -> Option<String> {
+    std::env::var("DEEPSEEK_TOKEN")
+        .ok()
+        .or_else(|| std::fs::read_to_string(".deepseek_token").ok())
+        .and_then(|contents| {
+            contents
+                .lines()
+                .map(|l| l.trim())
+                .find(|l| !l.is_empty())
+                .map(|l| l.to_string())
+        })
+}
+
+/// A resource is visible to its owner; with auth disabled (`caller` is `None`) everything is
+pub(super) fn visible_to(resource_owner: &Option<String>, caller: Option<&str>) -> bool {
+    caller.is_none() || resource_owner.as_deref() == caller
+}
+
 impl ThreadState {
     pub fn get_messages(&self) -> &[ThreadMessage] {
         &self.messages
@@ -231,6 +401,8 @@ impl Clone for ThreadState {
             model: self.model.clone(),
             qwen_chat_id: self.qwen_chat_id.clone(),
             qwen_parent_id: self.qwen_parent_id.clone(),
+            continuation: self.continuation.clone(),
+            owner: self.owner.clone(),
         }
     }
 }
@@ -0,0 +1,263 @@
+use super::error::ApiError;
+use super::state::ThreadState;
+use async_trait::async_trait;
+use reverse_api::qwen::models::QwenFile;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+/// Storage for threads and uploaded file references.
+///
+/// Writes are upserts: `put_thread` replaces any thread stored under the same id.
+#[async_trait]
+pub trait ThreadStore: Send + Sync {
+    async fn put_thread(&self, thread_id: &str, thread: &ThreadState) -> Result<(), ApiError>;
+    async fn get_thread(&self, thread_id: &str) -> Result<Option<ThreadState>, ApiError>;
+    async fn list_threads(&self) -> Result<Vec<(String, ThreadState)>, ApiError>;
+    /// Returns `false` if there was no such thread
+    async fn delete_thread(&self, thread_id: &str) -> Result<bool, ApiError>;
+
+    async fn put_file(&self, file: &StoredFile) -> Result<(), ApiError>;
+    async fn get_file(&self, file_id: &str) -> Result<Option<StoredFile>, ApiError>;
+}
+
+/// An uploaded Qwen file together with the API key fingerprint that uploaded it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredFile {
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(flatten)]
+    pub file: QwenFile,
+}
+
+/// Keeps everything in memory; all data is lost when the server stops
+#[derive(Default)]
+pub struct MemoryStore {
+    threads: RwLock<HashMap<String, ThreadState>>,
+    files: RwLock<HashMap<String, StoredFile>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ThreadStore for MemoryStore {
+    async fn put_thread(&self, thread_id: &str, thread: &ThreadState) -> Result<(), ApiError> {
+        let mut threads = self.threads.write().await;
+        threads.insert(thread_id.to_string(), thread.clone());
+        Ok(())
+    }
+
+    async fn get_thread(&self, thread_id: &str) -> Result<Option<ThreadState>, ApiError> {
+        let threads = self.threads.read().await;
+        Ok(threads.get(thread_id).cloned())
+    }
+
+    async fn list_threads(&self) -> Result<Vec<(String, ThreadState)>, ApiError> {
+        let threads = self.threads.read().await;
+        Ok(threads
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect())
+    }
+
+    async fn delete_thread(&self, thread_id: &str) -> Result<bool, ApiError> {
+        let mut threads = self.threads.write().await;
+        Ok(threads.remove(thread_id).is_some())
+    }
+
+    async fn put_file(&self, file: &StoredFile) -> Result<(), ApiError> {
+        let mut files = self.files.write().await;
+        files.insert(file.file.id.clone(), file.clone());
+        Ok(())
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<Option<StoredFile>, ApiError> {
+        let files = self.files.read().await;
+        Ok(files.get(file_id).cloned())
+    }
+}
+
+/// Stores one JSON document per thread and per uploaded file under a data directory:
+///
+/// ```text
+/// <data_dir>/threads/<thread_id>.json
+/// <data_dir>/files/<file_id>.json
+/// ```
+///
+/// Each write goes to a temporary file that is then renamed over the old one,
+/// so a crash never leaves a half-written document behind.
+pub struct FileStore {
+    threads_dir: PathBuf,
+    files_dir: PathBuf,
+    // Serializes writers; readers only ever see complete files thanks to the rename
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl FileStore {
+    pub async fn open(data_dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let threads_dir = data_dir.as_ref().join("threads");
+        let files_dir = data_dir.as_ref().join("files");
+        tokio::fs::create_dir_all(&threads_dir).await?;
+        tokio::fs::create_dir_all(&files_dir).await?;
+
+        Ok(Self {
+            threads_dir,
+            files_dir,
+            write_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Path for `id` inside `dir`, or `None` if the id could escape the directory
+    fn document_path(dir: &Path, id: &str) -> Option<PathBuf> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| dir.join(format!("{}.json", id)))
+    }
+
+    async fn write_document<T: serde::Serialize>(
+        &self,
+        dir: &Path,
+        id: &str,
+        value: &T,
+    ) -> Result<(), ApiError> {
+        let path = Self::document_path(dir, id)
+            .ok_or_else(|| ApiError::bad_request(format!("Invalid id: {}", id)))?;
+        let json = serde_json::to_vec_pretty(value)
+            .map_err(|e| ApiError::internal_error(format!("Could not serialize {}: {}", id, e)))?;
+
+        let _guard = self.write_lock.lock().await;
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+
+    async fn read_document<T: serde::de::DeserializeOwned>(
+        dir: &Path,
+        id: &str,
+    ) -> Result<Option<T>, ApiError> {
+        let Some(path) = Self::document_path(dir, id) else {
+            return Ok(None);
+        };
+
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+                ApiError::internal_error(format!("Corrupt document {}: {}", path.display(), e))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl ThreadStore for FileStore {
+    async fn put_thread(&self, thread_id: &str, thread: &ThreadState) -> Result<(), ApiError> {
+        self.write_document(&self.threads_dir, thread_id, thread)
+            .await
+    }
+
+    async fn get_thread(&self, thread_id: &str) -> Result<Option<ThreadState>, ApiError> {
+        Self::read_document(&self.threads_dir, thread_id).await
+    }
+
+    async fn list_threads(&self) -> Result<Vec<(String, ThreadState)>, ApiError> {
+        let mut threads = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.threads_dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(thread_id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if let Some(thread) = Self::read_document(&self.threads_dir, thread_id).await? {
+                threads.push((thread_id.to_string(), thread));
+            }
+        }
+
+        Ok(threads)
+    }
+
+    async fn delete_thread(&self, thread_id: &str) -> Result<bool, ApiError> {
+        let Some(path) = Self::document_path(&self.threads_dir, thread_id) else {
+            return Ok(false);
+        };
+
+        let _guard = self.write_lock.lock().await;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put_file(&self, file: &StoredFile) -> Result<(), ApiError> {
+        self.write_document(&self.files_dir, &file.file.id, file)
+            .await
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<Option<StoredFile>, ApiError> {
+        Self::read_document(&self.files_dir, file_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_thread() -> ThreadState {
+        ThreadState {
+            created_at: 1,
+            metadata: Some(serde_json::json!({ "title": "test" })),
+            messages: vec![],
+            model: "qwen3-max".to_string(),
+            qwen_chat_id: Some("chat-1".to_string()),
+            qwen_parent_id: Some("resp-1".to_string()),
+            continuation: None,
+            owner: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn file_store_survives_reopen() {
+        let dir = std::env::temp_dir().join(format!("api-store-{}", uuid::Uuid::new_v4()));
+
+        let store = FileStore::open(&dir).await.unwrap();
+        store
+            .put_thread("thread-1", &sample_thread())
+            .await
+            .unwrap();
+        drop(store);
+
+        let store = FileStore::open(&dir).await.unwrap();
+        let thread = store.get_thread("thread-1").await.unwrap().unwrap();
+        assert_eq!(thread.qwen_chat_id.as_deref(), Some("chat-1"));
+        assert_eq!(store.list_threads().await.unwrap().len(), 1);
+
+        assert!(store.delete_thread("thread-1").await.unwrap());
+        assert!(store.get_thread("thread-1").await.unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn file_store_rejects_path_like_ids() {
+        let dir = std::env::temp_dir().join(format!("api-store-{}", uuid::Uuid::new_v4()));
+        let store = FileStore::open(&dir).await.unwrap();
+
+        assert!(store.get_thread("../threads/x").await.unwrap().is_none());
+        assert!(store.put_thread("../x", &sample_thread()).await.is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
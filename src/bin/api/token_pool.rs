@@ -0,0 +1,335 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a token is benched after the upstream rejects it as unauthorized or expired
+const AUTH_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+/// How long a token is benched after hitting a rate limit or quota, unless the upstream
+/// said how long to wait
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+/// Other errors only bench a token once they happen this many times in a row
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Why a request made with a token failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Token is invalid or expired (401/403)
+    Auth,
+    /// Token hit a rate limit or quota (429)
+    RateLimited,
+    /// Anything else (network errors, upstream 5xx, bad requests)
+    Other,
+}
+
+impl FailureKind {
+    /// Whether the same request is worth retrying with a different token
+    pub fn is_token_specific(self) -> bool {
+        matches!(self, FailureKind::Auth | FailureKind::RateLimited)
+    }
+
+    /// Classify a Qwen client error by its variant
+    pub fn classify(error: &reverse_api::QwenError) -> Self {
+        match error {
+            reverse_api::QwenError::Unauthorized(_) => FailureKind::Auth,
+            reverse_api::QwenError::RateLimited { .. } => FailureKind::RateLimited,
+            _ => FailureKind::Other,
+        }
+    }
+}
+
+struct TokenEntry {
+    id: String,
+    token: String,
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    last_used_at: Option<u64>,
+    cooldown_until: Option<Instant>,
+}
+
+impl TokenEntry {
+    fn new(token: String) -> Self {
+        Self {
+            id: token_id(&token),
+            token,
+            successes: 0,
+            failures: 0,
+            consecutive_failures: 0,
+            last_error: None,
+            last_used_at: None,
+            cooldown_until: None,
+        }
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        self.cooldown_until.is_none_or(|until| until <= now)
+    }
+
+    fn info(&self, now: Instant) -> TokenInfo {
+        let cooldown_remaining = self
+            .cooldown_until
+            .filter(|until| *until > now)
+            .map(|until| (until - now).as_secs().max(1));
+
+        TokenInfo {
+            id: self.id.clone(),
+            token: mask_token(&self.token),
+            status: if cooldown_remaining.is_some() {
+                "cooling_down"
+            } else {
+                "healthy"
+            },
+            successes: self.successes,
+            failures: self.failures,
+            consecutive_failures: self.consecutive_failures,
+            last_error: self.last_error.clone(),
+            last_used_at: self.last_used_at,
+            cooldown_remaining_secs: cooldown_remaining,
+        }
+    }
+}
+
+/// Public view of a pooled token; the secret itself is masked
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenInfo {
+    pub id: String,
+    pub token: String,
+    pub status: &'static str,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldown_remaining_secs: Option<u64>,
+}
+
+/// A token handed out by [`TokenPool::acquire`]
+#[derive(Debug, Clone)]
+pub struct PooledToken {
+    pub id: String,
+    pub token: String,
+}
+
+/// Round-robin pool of Qwen tokens that benches tokens after auth or quota failures
+pub struct TokenPool {
+    entries: Mutex<Vec<TokenEntry>>,
+    cursor: Mutex<usize>,
+}
+
+impl TokenPool {
+    pub fn new(tokens: Vec<String>) -> Self {
+        let mut entries: Vec<TokenEntry> = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            if !entries.iter().any(|e| e.token == token) {
+                entries.push(TokenEntry::new(token));
+            }
+        }
+
+        Self {
+            entries: Mutex::new(entries),
+            cursor: Mutex::new(0),
+        }
+    }
+
+    pub async fn len(&self) -> usize {
+        self.entries.lock().await.len()
+    }
+
+    /// Add a token; adding one that is already pooled just returns its info
+    pub async fn add(&self, token: String) -> TokenInfo {
+        let mut entries = self.entries.lock().await;
+        let now = Instant::now();
+
+        if let Some(existing) = entries.iter().find(|e| e.token == token) {
+            return existing.info(now);
+        }
+
+        let entry = TokenEntry::new(token);
+        let info = entry.info(now);
+        entries.push(entry);
+        info
+    }
+
+    pub async fn remove(&self, id: &str) -> bool {
+        let mut entries = self.entries.lock().await;
+        let before = entries.len();
+        entries.retain(|e| e.id != id);
+        entries.len() != before
+    }
+
+    pub async fn list(&self) -> Vec<TokenInfo> {
+        let entries = self.entries.lock().await;
+        let now = Instant::now();
+        entries.iter().map(|e| e.info(now)).collect()
+    }
+
+    /// Next healthy token in round-robin order, skipping tokens in `exclude`.
+    /// Returns `None` if every remaining token is cooling down.
+    pub async fn acquire(&self, exclude: &[String]) -> Option<PooledToken> {
+        let mut entries = self.entries.lock().await;
+        if entries.is_empty() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let mut cursor = self.cursor.lock().await;
+        let len = entries.len();
+
+        for offset in 0..len {
+            let idx = (*cursor + offset) % len;
+            let entry = &mut entries[idx];
+            if entry.is_available(now) && !exclude.contains(&entry.id) {
+                *cursor = (idx + 1) % len;
+                entry.cooldown_until = None;
+                entry.last_used_at = Some(unix_now());
+                return Some(PooledToken {
+                    id: entry.id.clone(),
+                    token: entry.token.clone(),
+                });
+            }
+        }
+
+        None
+    }
+
+    pub async fn report_success(&self, id: &str) {
+        let mut entries = self.entries.lock().await;
+        if let Some(entry) = entries.iter_mut().find(|e| e.id == id) {
+            entry.successes += 1;
+            entry.consecutive_failures = 0;
+        }
+    }
+
+    /// Record a failed request. `retry_after` is the upstream's own estimate of when a
+    /// rate-limited token can be used again.
+    pub async fn report_failure(
+        &self,
+        id: &str,
+        kind: FailureKind,
+        retry_after: Option<Duration>,
+        error: &str,
+    ) {
+        let mut entries = self.entries.lock().await;
+        let Some(entry) = entries.iter_mut().find(|e| e.id == id) else {
+            return;
+        };
+
+        entry.failures += 1;
+        entry.consecutive_failures += 1;
+        entry.last_error = Some(error.to_string());
+
+        let cooldown = match kind {
+            FailureKind::Auth => Some(AUTH_COOLDOWN),
+            FailureKind::RateLimited => Some(retry_after.unwrap_or(RATE_LIMIT_COOLDOWN)),
+            FailureKind::Other if entry.consecutive_failures >= MAX_CONSECUTIVE_FAILURES => {
+                Some(FAILURE_COOLDOWN)
+            }
+            FailureKind::Other => None,
+        };
+
+        if let Some(cooldown) = cooldown {
+            entry.cooldown_until = Some(Instant::now() + cooldown);
+        }
+    }
+}
+
+/// Short stable id for a token, safe to show in API responses
+pub fn token_id(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    let hex: String = digest[..6].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("tok_{}", hex)
+}
+
+/// Keep only the first and last four characters of a secret
+fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() <= 12 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failover_skips_cooling_tokens() {
+        let pool = TokenPool::new(vec![
+            "token-aaaaaaaaaaaa".into(),
+            "token-bbbbbbbbbbbb".into(),
+        ]);
+
+        let first = pool.acquire(&[]).await.unwrap();
+        pool.report_failure(&first.id, FailureKind::Auth, None, "401 Unauthorized")
+            .await;
+
+        // Only the other token is handed out while the first one cools down
+        for _ in 0..3 {
+            let next = pool.acquire(&[]).await.unwrap();
+            assert_ne!(next.id, first.id);
+        }
+
+        let info = pool.list().await;
+        let benched = info.iter().find(|t| t.id == first.id).unwrap();
+        assert_eq!(benched.status, "cooling_down");
+        assert!(!benched.token.contains("aaaaaaaaaaaa"));
+    }
+
+    #[tokio::test]
+    async fn other_errors_only_bench_after_repeated_failures() {
+        let pool = TokenPool::new(vec!["only-token-123456".into()]);
+        let token = pool.acquire(&[]).await.unwrap();
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES - 1 {
+            pool.report_failure(&token.id, FailureKind::Other, None, "timeout")
+                .await;
+            assert!(pool.acquire(&[]).await.is_some());
+        }
+
+        pool.report_failure(&token.id, FailureKind::Other, None, "timeout")
+            .await;
+        assert!(pool.acquire(&[]).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn rate_limits_honour_retry_after() {
+        let pool = TokenPool::new(vec![
+            "token-aaaaaaaaaaaa".into(),
+            "token-bbbbbbbbbbbb".into(),
+        ]);
+        let told = pool.acquire(&[]).await.unwrap();
+        let untold = pool.acquire(&[]).await.unwrap();
+
+        let retry_after = Some(Duration::from_secs(600));
+        pool.report_failure(&told.id, FailureKind::RateLimited, retry_after, "429")
+            .await;
+        pool.report_failure(&untold.id, FailureKind::RateLimited, None, "429")
+            .await;
+
+        let info = pool.list().await;
+        let remaining = |id: &str| {
+            info.iter()
+                .find(|t| t.id == id)
+                .and_then(|t| t.cooldown_remaining_secs)
+                .unwrap()
+        };
+        assert!((590..=600).contains(&remaining(&told.id)));
+        assert!(remaining(&untold.id) <= RATE_LIMIT_COOLDOWN.as_secs());
+    }
+}
@@ -1,3 +1,4 @@
+use reverse_api::ChatType;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
@@ -40,13 +41,44 @@ pub struct CreateResponseRequest {
     #[serde(default)]
     #[allow(dead_code)]
     pub instructions: Option<String>,
+    /// Qwen chat mode: `t2t`, `search`, `deep_research`, `image_edit`, `web_dev`, `artifacts`, `travel`, `t2i`, `t2v`
+    #[serde(default)]
+    pub chat_type: Option<ChatType>,
+    /// Search the web; same as `chat_type: "search"`
+    #[serde(default)]
+    pub enable_search: bool,
+    #[serde(default)]
+    pub enable_thinking: bool,
+    /// Qwen only; must fit the model's thinking limit
+    #[serde(default)]
+    pub thinking_budget: Option<u32>,
     #[serde(default)]
-    #[allow(dead_code)]
     pub stream: bool,
     #[serde(default)]
     pub file_ids: Option<Vec<String>>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionRequest {
+    #[serde(default = "default_model")]
+    pub model: String,
+    pub messages: Vec<ChatCompletionMessage>,
+    /// Accepted for OpenAI compatibility and range-checked, but not sent upstream:
+    /// none of the web chat APIs expose sampling settings
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ChatCompletionMessage {
+    pub role: String,
+    pub content: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GenerateImageRequest {
     pub prompt: String,
@@ -58,6 +90,21 @@ pub struct GenerateImageRequest {
     pub thread_id: Option<String>,
     #[serde(default)]
     pub download: bool,
+    /// Number of images to generate
+    #[serde(default = "default_image_count")]
+    pub n: usize,
+    #[serde(default)]
+    pub response_format: ImageResponseFormat,
+}
+
+/// How images are returned in `data`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageResponseFormat {
+    #[default]
+    Url,
+    /// Base64 of the downloaded image, without `url`
+    B64Json,
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,12 +118,19 @@ pub struct GenerateVideoRequest {
     pub thread_id: Option<String>,
     #[serde(default)]
     pub download: bool,
+    /// Uploaded image (from `/v1/files`) used as the first frame
+    #[serde(default)]
+    pub image_file_id: Option<String>,
 }
 
 fn default_qwen_model() -> String {
     "qwen3-max".to_string()
 }
 
+fn default_image_count() -> usize {
+    1
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileUploadResponse {
     pub id: String,
@@ -87,22 +141,28 @@ pub struct FileUploadResponse {
 
 #[derive(Debug, Serialize)]
 pub struct GenerateImageResponse {
+    pub created: u64,
+    /// One entry per image, OpenAI style
+    pub data: Vec<ImageData>,
+    /// First image URL, kept for older clients
     pub image_url: String,
     pub prompt: String,
     pub chat_id: Option<String>,
     pub response_id: String,
+    /// Server URL of the first stored image
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub local_path: Option<String>,
+    pub media_url: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
-pub struct GenerateVideoResponse {
-    pub video_url: String,
-    pub prompt: String,
-    pub chat_id: Option<String>,
-    pub response_id: String,
+pub struct ImageData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub local_path: Option<String>,
+    pub b64_json: Option<String>,
+    /// `/v1/media/{id}` when the image was stored on this server (`download: true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_url: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -148,6 +208,54 @@ pub struct Response {
     pub response: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionResponse {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<ChatCompletionChoice>,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionChoice {
+    pub index: u32,
+    pub message: ChatCompletionMessage,
+    pub finish_reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Usage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionChunk {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<ChatCompletionChunkChoice>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionChunkChoice {
+    pub index: u32,
+    pub delta: ChatCompletionDelta,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ChatCompletionDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ListThreadsResponse {
     pub object: String,
@@ -162,6 +270,11 @@ pub struct ListMessagesResponse {
     pub has_more: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AddQwenTokenRequest {
+    pub token: String,
+}
+
 fn default_model() -> String {
     "qwen3-max".to_string()
 }
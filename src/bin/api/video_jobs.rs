@@ -0,0 +1,237 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tokio::task::AbortHandle;
+
+use super::state::visible_to;
+
+/// Finished jobs are dropped this long after their last update
+const KEEP_FINISHED_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoJobStatus {
+    /// Waiting for upstream to accept the task
+    Queued,
+    /// Upstream is rendering; polled in the background
+    Running,
+    Succeeded,
+    Failed,
+    /// Polling was stopped through `DELETE /v1/videos/{job_id}`
+    Cancelled,
+}
+
+impl VideoJobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            VideoJobStatus::Succeeded | VideoJobStatus::Failed | VideoJobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoJob {
+    pub id: String,
+    pub status: VideoJobStatus,
+    pub prompt: String,
+    pub model: String,
+    pub size: Option<String>,
+    /// Upstream task id, once the task was accepted
+    pub task_id: Option<String>,
+    /// Upstream estimate of the seconds left while running
+    pub remaining_secs: Option<u64>,
+    /// Latest status message from upstream
+    pub message: Option<String>,
+    pub video_url: Option<String>,
+    /// `/v1/media/{id}` once the video was stored on this server
+    pub media_url: Option<String>,
+    pub chat_id: Option<String>,
+    pub response_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    #[serde(skip)]
+    pub owner: Option<String>,
+}
+
+impl VideoJob {
+    pub fn new(owner: Option<&str>, prompt: String, model: String, size: Option<String>) -> Self {
+        let now = now_secs();
+        Self {
+            id: format!("video-{}", uuid::Uuid::new_v4()),
+            status: VideoJobStatus::Queued,
+            prompt,
+            model,
+            size,
+            task_id: None,
+            remaining_secs: None,
+            message: None,
+            video_url: None,
+            media_url: None,
+            chat_id: None,
+            response_id: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            owner: owner.map(|o| o.to_string()),
+        }
+    }
+}
+
+/// What the public dashboard shows about a job; prompts and URLs stay private
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoJobSummary {
+    pub id: String,
+    pub status: VideoJobStatus,
+    pub remaining_secs: Option<u64>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+struct JobEntry {
+    job: VideoJob,
+    /// Stops the background task on cancel
+    handle: Option<AbortHandle>,
+}
+
+/// Video jobs polled in the background, independent of the request that started them
+#[derive(Default)]
+pub struct VideoJobs {
+    jobs: RwLock<HashMap<String, JobEntry>>,
+}
+
+impl VideoJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn insert(&self, job: VideoJob) {
+        let mut jobs = self.jobs.write().await;
+
+        let now = now_secs();
+        jobs.retain(|_, entry| {
+            !entry.job.status.is_finished()
+                || now.saturating_sub(entry.job.updated_at) < KEEP_FINISHED_SECS
+        });
+
+        jobs.insert(job.id.clone(), JobEntry { job, handle: None });
+    }
+
+    /// Attach the task running the job; a job cancelled before this point is aborted right away
+    pub async fn set_handle(&self, job_id: &str, handle: AbortHandle) {
+        let mut jobs = self.jobs.write().await;
+        match jobs.get_mut(job_id) {
+            Some(entry) if !entry.job.status.is_finished() => entry.handle = Some(handle),
+            _ => handle.abort(),
+        }
+    }
+
+    /// Apply `f` to a job that is still in progress; finished jobs are left alone
+    /// so a late update cannot revive a cancelled job
+    pub async fn update(&self, job_id: &str, f: impl FnOnce(&mut VideoJob)) {
+        let mut jobs = self.jobs.write().await;
+        if let Some(entry) = jobs.get_mut(job_id) {
+            if entry.job.status.is_finished() {
+                return;
+            }
+            f(&mut entry.job);
+            entry.job.updated_at = now_secs();
+            if entry.job.status.is_finished() {
+                entry.handle = None;
+            }
+        }
+    }
+
+    /// Jobs of another API key are reported as missing
+    pub async fn get(&self, owner: Option<&str>, job_id: &str) -> Option<VideoJob> {
+        self.jobs
+            .read()
+            .await
+            .get(job_id)
+            .filter(|entry| visible_to(&entry.job.owner, owner))
+            .map(|entry| entry.job.clone())
+    }
+
+    /// Jobs visible to `owner`, newest first
+    pub async fn list(&self, owner: Option<&str>) -> Vec<VideoJob> {
+        let mut jobs: Vec<VideoJob> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|entry| visible_to(&entry.job.owner, owner))
+            .map(|entry| entry.job.clone())
+            .collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        jobs
+    }
+
+    pub async fn summaries(&self) -> Vec<VideoJobSummary> {
+        self.list(None)
+            .await
+            .into_iter()
+            .map(|job| VideoJobSummary {
+                id: job.id,
+                status: job.status,
+                remaining_secs: job.remaining_secs,
+                created_at: job.created_at,
+                updated_at: job.updated_at,
+            })
+            .collect()
+    }
+
+    /// Stop polling a job. Finished jobs are returned unchanged.
+    pub async fn cancel(&self, owner: Option<&str>, job_id: &str) -> Option<VideoJob> {
+        let mut jobs = self.jobs.write().await;
+        let entry = jobs
+            .get_mut(job_id)
+            .filter(|entry| visible_to(&entry.job.owner, owner))?;
+
+        if !entry.job.status.is_finished() {
+            if let Some(handle) = entry.handle.take() {
+                handle.abort();
+            }
+            entry.job.status = VideoJobStatus::Cancelled;
+            entry.job.remaining_secs = None;
+            entry.job.updated_at = now_secs();
+        }
+
+        Some(entry.job.clone())
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancelled_job_ignores_late_updates() {
+        let jobs = VideoJobs::new();
+        let job = VideoJob::new(Some("key-a"), "a cat".into(), "qwen3-max".into(), None);
+        let id = job.id.clone();
+        jobs.insert(job).await;
+
+        assert!(jobs.cancel(Some("key-b"), &id).await.is_none());
+        let cancelled = jobs.cancel(Some("key-a"), &id).await.unwrap();
+        assert_eq!(cancelled.status, VideoJobStatus::Cancelled);
+
+        // The background task may still report a result after the cancel
+        jobs.update(&id, |job| {
+            job.status = VideoJobStatus::Succeeded;
+            job.video_url = Some("https://example.com/v.mp4".into());
+        })
+        .await;
+
+        let job = jobs.get(None, &id).await.unwrap();
+        assert_eq!(job.status, VideoJobStatus::Cancelled);
+        assert!(job.video_url.is_none());
+    }
+}
@@ -1,7 +1,15 @@
 mod api;
 
-use reverse_api::Logger;
+use api::auth::AuthConfig;
+use api::media::MediaStore;
+use api::store::{FileStore, MemoryStore, ThreadStore};
+use reverse_api::{Logger, QwenClient, UploadLimits};
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often expired media is removed while the server runs
+const MEDIA_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 fn print_usage() {
     println!("Usage: api_server [OPTIONS]");
@@ -9,6 +17,15 @@ fn print_usage() {
     println!("Options:");
     println!("  --host <HOST>      Server host (default: 0.0.0.0)");
     println!("  --port <PORT>      Server port (default: 6969)");
+    println!("  --store <KIND>     Thread storage: memory or file (default: memory)");
+    println!("  --data-dir <DIR>   Data directory for --store file (default: ./data)");
+    println!("  --media-dir <DIR>  Generated media directory (default: ./generated)");
+    println!("  --media-retention-hours <N>  Prune media after N hours, 0 = never (default: 24)");
+    println!("  --upload-limits <LIST>  Upload limits in MiB, e.g. vision=20,video=500");
+    println!("  --qwen-base-url <URL>  Qwen API base URL (default: https://chat.qwen.ai)");
+    println!("  --proxy <URL>      Proxy for upstream requests, e.g. http://proxy:3128");
+    println!("  --api-keys-file <PATH>  File with one accepted API key per line");
+    println!("  --public-health    Serve GET /health without an API key");
     println!("  --help             Show this help message");
     println!();
     println!();
@@ -16,6 +33,47 @@ fn print_usage() {
     println!("  api_server");
     println!("  api_server --port 8080");
     println!("  api_server --host 127.0.0.1 --port 8080");
+    println!("  api_server --store file --data-dir /var/lib/api_server");
+    println!("  api_server --media-dir /var/lib/api_server/media --media-retention-hours 72");
+    println!("  api_server --proxy http://proxy.corp:3128");
+    println!("  API_KEYS=key1,key2 api_server --public-health");
+}
+
+/// Parse `class=MiB` pairs; classes that are not listed keep their default limit
+fn parse_upload_limits(spec: &str) -> Result<UploadLimits, String> {
+    let mut limits = UploadLimits::default();
+    for pair in spec.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let (class, mib) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected class=MiB, got {}", pair))?;
+        let bytes = mib
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| format!("invalid size for {}: {}", class, mib))?
+            * 1024
+            * 1024;
+        match class.trim() {
+            "vision" => limits.vision = bytes,
+            "video" => limits.video = bytes,
+            "audio" => limits.audio = bytes,
+            "document" => limits.document = bytes,
+            other => return Err(format!("unknown file class: {}", other)),
+        }
+    }
+    Ok(limits)
+}
+
+/// Remove expired media now and then every [`MEDIA_PRUNE_INTERVAL`]
+async fn prune_media(media: Arc<MediaStore>) {
+    let mut interval = tokio::time::interval(MEDIA_PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match media.prune().await {
+            Ok(0) => {}
+            Ok(removed) => Logger::info(&format!("Media: removed {} expired file(s)", removed)),
+            Err(err) => Logger::error(&format!("Could not prune media dir: {}", err)),
+        }
+    }
 }
 
 #[tokio::main]
@@ -23,6 +81,15 @@ async fn main() {
     let args: Vec<String> = env::args().collect();
     let mut host = "0.0.0.0".to_string();
     let mut port = 6969u16;
+    let mut store_kind = "memory".to_string();
+    let mut data_dir = "./data".to_string();
+    let mut media_dir = "./generated".to_string();
+    let mut media_retention_hours = 24u64;
+    let mut upload_limits = UploadLimits::default();
+    let mut qwen_base_url: Option<String> = None;
+    let mut proxy: Option<String> = None;
+    let mut api_keys_file: Option<String> = None;
+    let mut public_health = false;
 
     let mut i = 1;
     while i < args.len() {
@@ -55,6 +122,94 @@ async fn main() {
                     std::process::exit(1);
                 }
             }
+            "--store" => {
+                if i + 1 < args.len() {
+                    store_kind = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    Logger::error("--store requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--data-dir" => {
+                if i + 1 < args.len() {
+                    data_dir = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    Logger::error("--data-dir requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--media-dir" => {
+                if i + 1 < args.len() {
+                    media_dir = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    Logger::error("--media-dir requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--media-retention-hours" => {
+                if i + 1 < args.len() {
+                    match args[i + 1].parse::<u64>() {
+                        Ok(hours) => media_retention_hours = hours,
+                        Err(_) => {
+                            Logger::error(&format!("Invalid retention hours: {}", args[i + 1]));
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                } else {
+                    Logger::error("--media-retention-hours requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--upload-limits" => {
+                if i + 1 < args.len() {
+                    match parse_upload_limits(&args[i + 1]) {
+                        Ok(limits) => upload_limits = limits,
+                        Err(err) => {
+                            Logger::error(&format!("Invalid --upload-limits: {}", err));
+                            std::process::exit(1);
+                        }
+                    }
+                    i += 2;
+                } else {
+                    Logger::error("--upload-limits requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--qwen-base-url" => {
+                if i + 1 < args.len() {
+                    qwen_base_url = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    Logger::error("--qwen-base-url requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--proxy" => {
+                if i + 1 < args.len() {
+                    proxy = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    Logger::error("--proxy requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--api-keys-file" => {
+                if i + 1 < args.len() {
+                    api_keys_file = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    Logger::error("--api-keys-file requires a value");
+                    std::process::exit(1);
+                }
+            }
+            "--public-health" => {
+                public_health = true;
+                i += 1;
+            }
             _ => {
                 Logger::error(&format!("Unknown option: {}", args[i]));
                 println!();
@@ -78,8 +233,84 @@ async fn main() {
     Logger::info("=======================");
     Logger::info(&format!("Host: {}", host));
     Logger::info(&format!("Port: {}", port));
-    Logger::info("Supported Models: Qwen (qwen-*)");
-    if let Err(err) = api::server::run(&host, port).await {
+
+    let store: Arc<dyn ThreadStore> = match store_kind.as_str() {
+        "memory" => Arc::new(MemoryStore::new()),
+        "file" => match FileStore::open(&data_dir).await {
+            Ok(store) => {
+                Logger::info(&format!("Storage: file ({})", data_dir));
+                Arc::new(store)
+            }
+            Err(err) => {
+                Logger::error(&format!("Could not open data dir {}: {}", data_dir, err));
+                std::process::exit(1);
+            }
+        },
+        other => {
+            Logger::error(&format!(
+                "Unknown store: {} (expected memory or file)",
+                other
+            ));
+            std::process::exit(1);
+        }
+    };
+
+    let auth = match AuthConfig::load(api_keys_file.as_deref(), public_health) {
+        Ok(auth) => auth,
+        Err(err) => {
+            Logger::error(&format!("Could not read API keys file: {}", err));
+            std::process::exit(1);
+        }
+    };
+    if auth.is_enabled() {
+        Logger::info(&format!("Auth: {} API key(s) configured", auth.key_count()));
+    } else {
+        Logger::info("Auth: disabled (no API keys configured)");
+    }
+
+    let retention =
+        (media_retention_hours > 0).then(|| Duration::from_secs(media_retention_hours * 60 * 60));
+    let media = match MediaStore::open(&media_dir, retention).await {
+        Ok(media) => Arc::new(media),
+        Err(err) => {
+            Logger::error(&format!("Could not open media dir {}: {}", media_dir, err));
+            std::process::exit(1);
+        }
+    };
+    if retention.is_some() {
+        tokio::spawn(prune_media(media.clone()));
+    }
+    Logger::info(&format!(
+        "Media: {} (retention: {})",
+        media.root().display(),
+        match retention {
+            Some(_) => format!("{}h", media_retention_hours),
+            None => "forever".to_string(),
+        }
+    ));
+
+    let mut qwen = QwenClient::builder();
+    if let Some(base_url) = &qwen_base_url {
+        if url::Url::parse(base_url).is_err() {
+            Logger::error(&format!("Invalid --qwen-base-url: {}", base_url));
+            std::process::exit(1);
+        }
+        Logger::info(&format!("Qwen base URL: {}", base_url));
+        qwen = qwen.base_url(base_url.clone());
+    }
+    if let Some(proxy) = &proxy {
+        Logger::info(&format!("Proxy: {}", proxy));
+        qwen = qwen.proxy(proxy.clone());
+    }
+    let qwen = match qwen.share_http_client() {
+        Ok(qwen) => qwen,
+        Err(err) => {
+            Logger::error(&format!("Could not create the Qwen HTTP client: {}", err));
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(err) = api::server::run(&host, port, store, auth, media, upload_limits, qwen).await {
         Logger::error(&format!("API server failed: {}", err));
         std::process::exit(1);
     }
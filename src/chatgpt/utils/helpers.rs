@@ -1,3 +1,5 @@
+use crate::sse;
+
 /// Utility functions similar to Python's Utils class
 pub struct Utils;
 
@@ -97,46 +99,28 @@ impl Utils {
     pub fn parse_event_stream(stream_data: &str) -> String {
         let mut result = Vec::new();
 
-        for line in stream_data.lines() {
-            if let Some(stripped) = line.strip_prefix("data:") {
-                let data_str = stripped.trim();
+        for event in sse::parse(stream_data) {
+            let data_str = event.data.trim();
 
-                if data_str == "[DONE]" {
-                    break;
-                }
+            if data_str == "[DONE]" {
+                break;
+            }
 
-                if let Ok(data) = serde_json::from_str::<serde_json::Value>(data_str) {
-                    // Handle direct append operations
-                    if let (Some("append"), Some("/message/content/parts/0"), Some(value)) = (
-                        data.get("o").and_then(|v| v.as_str()),
-                        data.get("p").and_then(|v| v.as_str()),
-                        data.get("v").and_then(|v| v.as_str()),
-                    ) {
-                        result.push(value.to_string());
-                    }
-                    // Handle patch operations with list of operations
-                    else if let (Some(op), Some(operations)) = (
-                        data.get("o").and_then(|v| v.as_str()),
-                        data.get("v").and_then(|v| v.as_array()),
-                    ) {
-                        if op == "patch" {
-                            for operation in operations {
-                                if let (
-                                    Some("append"),
-                                    Some("/message/content/parts/0"),
-                                    Some(value),
-                                ) = (
-                                    operation.get("o").and_then(|v| v.as_str()),
-                                    operation.get("p").and_then(|v| v.as_str()),
-                                    operation.get("v").and_then(|v| v.as_str()),
-                                ) {
-                                    result.push(value.to_string());
-                                }
-                            }
-                        }
-                    }
-                    // Handle 'v' field containing list of operations
-                    else if let Some(operations) = data.get("v").and_then(|v| v.as_array()) {
+            if let Ok(data) = serde_json::from_str::<serde_json::Value>(data_str) {
+                // Handle direct append operations
+                if let (Some("append"), Some("/message/content/parts/0"), Some(value)) = (
+                    data.get("o").and_then(|v| v.as_str()),
+                    data.get("p").and_then(|v| v.as_str()),
+                    data.get("v").and_then(|v| v.as_str()),
+                ) {
+                    result.push(value.to_string());
+                }
+                // Handle patch operations with list of operations
+                else if let (Some(op), Some(operations)) = (
+                    data.get("o").and_then(|v| v.as_str()),
+                    data.get("v").and_then(|v| v.as_array()),
+                ) {
+                    if op == "patch" {
                         for operation in operations {
                             if let (Some("append"), Some("/message/content/parts/0"), Some(value)) = (
                                 operation.get("o").and_then(|v| v.as_str()),
@@ -148,6 +132,18 @@ impl Utils {
                         }
                     }
                 }
+                // Handle 'v' field containing list of operations
+                else if let Some(operations) = data.get("v").and_then(|v| v.as_array()) {
+                    for operation in operations {
+                        if let (Some("append"), Some("/message/content/parts/0"), Some(value)) = (
+                            operation.get("o").and_then(|v| v.as_str()),
+                            operation.get("p").and_then(|v| v.as_str()),
+                            operation.get("v").and_then(|v| v.as_str()),
+                        ) {
+                            result.push(value.to_string());
+                        }
+                    }
+                }
             }
         }
 
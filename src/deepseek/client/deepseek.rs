@@ -1,8 +1,10 @@
+use super::stream::{read_body, Delta};
 use crate::deepseek::error::{DeepSeekError, Result};
 use crate::deepseek::models::{DeepSeekChatRequest, DeepSeekResponse, ExtraData};
 use crate::deepseek::signature::{DeepSeekHash, DeepSeekSignature};
+use crate::events::{ClientEvent, EventSink, NoopSink};
+use crate::recording::{RecordedRequest, RecordedResponse, Recorder};
 use base64::Engine as _;
-use futures_util::stream::StreamExt;
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use rquest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE, COOKIE};
@@ -90,6 +92,8 @@ pub struct DeepSeekClient {
     client: rquest::Client,
     deepseek_hash: Arc<Mutex<DeepSeekHash>>,
     access_token_cache: Arc<Mutex<HashMap<String, (String, u64)>>>,
+    events: Arc<dyn EventSink>,
+    recorder: Recorder,
 }
 
 impl DeepSeekClient {
@@ -100,9 +104,24 @@ impl DeepSeekClient {
             client: rquest::Client::builder().cookie_store(true).build()?,
             deepseek_hash: Arc::new(Mutex::new(deepseek_hash)),
             access_token_cache: Arc::new(Mutex::new(HashMap::new())),
+            events: Arc::new(NoopSink),
+            recorder: Recorder::from_env(),
         })
     }
 
+    /// Report streamed answer deltas to `events` (nothing is printed by default)
+    pub fn with_event_sink(mut self, events: Arc<dyn EventSink>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Write every streamed completion to `dir` as a fixture, see [`crate::recording`].
+    /// Defaults to the directory in `REVERSE_API_RECORD_DIR`, if set.
+    pub fn record_to(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.recorder = Recorder::to_dir(dir);
+        self
+    }
+
     async fn acquire_token(&self, refresh_token: &str) -> Result<String> {
         let mut cache = self.access_token_cache.lock().await;
         if let Some((token, expiry)) = cache.get(refresh_token) {
@@ -259,6 +278,37 @@ impl DeepSeekClient {
         &self,
         message: &str,
         extra_data: Option<&ExtraData>,
+    ) -> Result<DeepSeekResponse> {
+        self.start_convo_with_options(message, extra_data, false, false)
+            .await
+    }
+
+    /// Search the web before answering; the pages used are in `search_results`
+    pub async fn start_convo_with_search(
+        &self,
+        message: &str,
+        extra_data: Option<&ExtraData>,
+    ) -> Result<DeepSeekResponse> {
+        self.start_convo_with_options(message, extra_data, true, false)
+            .await
+    }
+
+    /// Use deep thinking (DeepSeek-R1); the reasoning trace is in `thinking_content`
+    pub async fn start_convo_with_thinking(
+        &self,
+        message: &str,
+        extra_data: Option<&ExtraData>,
+    ) -> Result<DeepSeekResponse> {
+        self.start_convo_with_options(message, extra_data, false, true)
+            .await
+    }
+
+    pub async fn start_convo_with_options(
+        &self,
+        message: &str,
+        extra_data: Option<&ExtraData>,
+        search_enabled: bool,
+        thinking_enabled: bool,
     ) -> Result<DeepSeekResponse> {
         let access_token = self.acquire_token(&self.api_key).await?;
 
@@ -283,8 +333,8 @@ impl DeepSeekClient {
             parent_message_id: extra_data.map(|d| d.message_id.clone()),
             prompt: message.to_string(),
             ref_file_ids: vec![],
-            search_enabled: false,
-            thinking_enabled: false,
+            search_enabled,
+            thinking_enabled,
         };
 
         let url = "https://chat.deepseek.com/api/v0/chat/completion";
@@ -300,6 +350,12 @@ impl DeepSeekClient {
         for (key, value) in FAKE_HEADERS {
             headers.insert(*key, HeaderValue::from_static(value));
         }
+        let recorded = RecordedRequest::new(
+            "POST",
+            url,
+            &headers,
+            serde_json::to_value(&deepseek_request).ok(),
+        );
 
         let response = self
             .client
@@ -318,56 +374,29 @@ impl DeepSeekClient {
                 .unwrap_or("")
                 .contains("text/event-stream")
             {
-                // Handle streaming response
-                let mut stream = response.bytes_stream();
-                let mut content = String::new();
-                let mut message_id = String::new();
-
-                while let Some(chunk) = stream.next().await {
-                    let chunk = chunk?;
-                    let text = String::from_utf8_lossy(&chunk);
-
-                    for line in text.lines() {
-                        if line.starts_with("data: ") {
-                            let data = line.strip_prefix("data: ").unwrap().trim();
-                            if data.is_empty() || data == "[DONE]" {
-                                continue;
-                            }
-
-                            if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
-                                // Handle "ready" event with message IDs
-                                if let Some(resp_msg_id) = json["response_message_id"].as_i64() {
-                                    message_id = resp_msg_id.to_string();
-                                }
-
-                                // Handle content updates - DeepSeek format: {"v": "text", "p": "response/content", "o": "APPEND"}
-                                if let Some(text_value) = json["v"].as_str() {
-                                    if let Some(path) = json["p"].as_str() {
-                                        if path.contains("response/content") {
-                                            content.push_str(text_value);
-                                            print!("{}", text_value);
-                                            std::io::Write::flush(&mut std::io::stdout()).ok();
-                                        }
-                                    } else if !text_value.is_empty() {
-                                        // Sometimes "v" comes without "p"
-                                        content.push_str(text_value);
-                                        print!("{}", text_value);
-                                        std::io::Write::flush(&mut std::io::stdout()).ok();
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-
-                println!(); // New line after streaming
+                let head = RecordedResponse::head(status.as_u16(), response.headers());
+                let body = self.recorder.tap(
+                    "deepseek-completion",
+                    recorded,
+                    head,
+                    response.bytes_stream(),
+                );
+                let parser = read_body(body, |delta| match delta {
+                    Delta::Thinking(text) => self.events.emit(&ClientEvent::ThinkingDelta(text)),
+                    Delta::Answer(text) => self.events.emit(&ClientEvent::AnswerDelta(text)),
+                })
+                .await?;
+
+                self.events.emit(&ClientEvent::AnswerFinished);
 
                 return Ok(DeepSeekResponse {
-                    response: Some(content),
+                    response: Some(parser.content),
                     extra_data: ExtraData {
                         session_id,
-                        message_id,
+                        message_id: parser.message_id,
                     },
+                    thinking_content: Some(parser.thinking).filter(|t| !t.is_empty()),
+                    search_results: Some(parser.search_results).filter(|r| !r.is_empty()),
                 });
             }
         }
@@ -1 +1,2 @@
 pub mod deepseek;
+mod stream;
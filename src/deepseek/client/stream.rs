@@ -0,0 +1,263 @@
+use crate::deepseek::models::SearchResult;
+use crate::sse;
+use futures_util::stream::{Stream, StreamExt};
+use serde_json::Value;
+
+/// Which part of the response an append-only patch writes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Content,
+    Thinking,
+    Other,
+}
+
+/// A piece of text decoded from one SSE event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Delta {
+    Thinking(String),
+    Answer(String),
+}
+
+/// Folds DeepSeek's JSON-patch style SSE events into a response.
+///
+/// Events look like `{"p": "response/content", "o": "APPEND", "v": "text"}`; later
+/// events often drop `p` and keep appending to the last path. Newer sessions send the
+/// answer as `response/fragments`, each fragment typed `THINK`, `RESPONSE` or `SEARCH`.
+#[derive(Debug)]
+pub(crate) struct StreamParser {
+    target: Target,
+    pub content: String,
+    pub thinking: String,
+    pub search_results: Vec<SearchResult>,
+    pub message_id: String,
+}
+
+impl StreamParser {
+    pub fn new() -> Self {
+        Self {
+            target: Target::Content,
+            content: String::new(),
+            thinking: String::new(),
+            search_results: Vec::new(),
+            message_id: String::new(),
+        }
+    }
+
+    /// Apply one decoded `data:` payload, returning the text it added, in order
+    pub fn feed(&mut self, json: &Value) -> Vec<Delta> {
+        if let Some(id) = json["response_message_id"].as_i64() {
+            self.message_id = id.to_string();
+        }
+
+        if let Some(path) = json["p"].as_str() {
+            self.target = match path {
+                p if p.ends_with("thinking_content") => Target::Thinking,
+                p if p.ends_with("response/content") => Target::Content,
+                p if p.starts_with("response/fragments/") && p.ends_with("/content") => self.target,
+                "response/search_results" => {
+                    self.push_search_results(&json["v"]);
+                    Target::Other
+                }
+                "response/fragments" => {
+                    return self.push_fragments(&json["v"]);
+                }
+                _ => Target::Other,
+            };
+        } else if let Some(fragments) = json["v"]["response"]["fragments"].as_array() {
+            // Snapshot of the whole response sent when a session starts
+            if let Some(id) = json["v"]["response"]["message_id"].as_i64() {
+                self.message_id = id.to_string();
+            }
+            return self.push_fragments(&Value::Array(fragments.clone()));
+        }
+
+        json["v"]
+            .as_str()
+            .filter(|t| !t.is_empty())
+            .and_then(|text| self.append(text))
+            .into_iter()
+            .collect()
+    }
+
+    fn append(&mut self, text: &str) -> Option<Delta> {
+        match self.target {
+            Target::Content => {
+                self.content.push_str(text);
+                Some(Delta::Answer(text.to_string()))
+            }
+            Target::Thinking => {
+                self.thinking.push_str(text);
+                Some(Delta::Thinking(text.to_string()))
+            }
+            Target::Other => None,
+        }
+    }
+
+    /// A snapshot may carry several fragments; each one's text is a delta of its own
+    fn push_fragments(&mut self, fragments: &Value) -> Vec<Delta> {
+        let mut deltas = Vec::new();
+
+        for fragment in fragments.as_array().into_iter().flatten() {
+            match fragment["type"].as_str() {
+                Some("THINK") => self.target = Target::Thinking,
+                Some("RESPONSE") => self.target = Target::Content,
+                Some("SEARCH") => {
+                    self.push_search_results(&fragment["results"]);
+                    self.target = Target::Other;
+                }
+                _ => self.target = Target::Other,
+            }
+            if let Some(text) = fragment["content"].as_str().filter(|t| !t.is_empty()) {
+                deltas.extend(self.append(text));
+            }
+        }
+
+        deltas
+    }
+
+    fn push_search_results(&mut self, results: &Value) {
+        for result in results.as_array().into_iter().flatten() {
+            if let Ok(result) = serde_json::from_value::<SearchResult>(result.clone()) {
+                if !self.search_results.iter().any(|r| r.url == result.url) {
+                    self.search_results.push(result);
+                }
+            }
+        }
+    }
+}
+
+/// Read a whole SSE body into a parser, passing each piece of text to `on_delta`
+pub(crate) async fn read_body<S, B, E>(
+    body: S,
+    mut on_delta: impl FnMut(Delta),
+) -> std::result::Result<StreamParser, E>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]> + Send,
+    E: Send,
+{
+    let mut stream = Box::pin(sse::events(body));
+    let mut parser = StreamParser::new();
+
+    while let Some(event) = stream.next().await {
+        let data = event?.data;
+        if data.is_empty() || data == "[DONE]" {
+            continue;
+        }
+
+        if let Ok(json) = serde_json::from_str::<Value>(&data) {
+            for delta in parser.feed(&json) {
+                on_delta(delta);
+            }
+        }
+    }
+
+    Ok(parser)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::fixture;
+    use serde_json::json;
+
+    #[test]
+    fn bare_values_follow_the_last_path() {
+        let mut parser = StreamParser::new();
+        let events = [
+            json!({"response_message_id": 2}),
+            json!({"p": "response/thinking_content", "o": "APPEND", "v": "Let me "}),
+            json!({"v": "think."}),
+            json!({"p": "response/search_results", "o": "SET", "v": [
+                {"url": "https://example.com", "title": "Example", "snippet": "..."}
+            ]}),
+            json!({"p": "response/content", "o": "APPEND", "v": "Hello"}),
+            json!({"v": " world"}),
+        ];
+        for event in &events {
+            parser.feed(event);
+        }
+
+        assert_eq!(parser.thinking, "Let me think.");
+        assert_eq!(parser.content, "Hello world");
+        assert_eq!(parser.message_id, "2");
+        assert_eq!(parser.search_results.len(), 1);
+    }
+
+    #[test]
+    fn fragments_are_split_by_type() {
+        let mut parser = StreamParser::new();
+        let events = [
+            json!({"v": {"response": {"message_id": 4, "fragments": [
+                {"type": "THINK", "content": "Hmm"}
+            ]}}}),
+            json!({"p": "response/fragments/-1/content", "o": "APPEND", "v": "..."}),
+            json!({"p": "response/fragments", "o": "APPEND", "v": [
+                {"type": "RESPONSE", "content": "Answer"}
+            ]}),
+            json!({"p": "response/fragments/-1/content", "v": "!"}),
+        ];
+        for event in &events {
+            parser.feed(event);
+        }
+
+        assert_eq!(parser.thinking, "Hmm...");
+        assert_eq!(parser.content, "Answer!");
+        assert_eq!(parser.message_id, "4");
+    }
+
+    #[test]
+    fn every_fragment_of_a_snapshot_is_a_delta() {
+        let mut parser = StreamParser::new();
+
+        let deltas = parser.feed(&json!({"v": {"response": {"fragments": [
+            {"type": "THINK", "content": "Short thought."},
+            {"type": "RESPONSE", "content": "Quick answer"}
+        ]}}}));
+
+        assert_eq!(
+            deltas,
+            [
+                Delta::Thinking("Short thought.".into()),
+                Delta::Answer("Quick answer".into()),
+            ]
+        );
+        assert_eq!(parser.thinking, "Short thought.");
+        assert_eq!(parser.content, "Quick answer");
+    }
+
+    #[tokio::test]
+    async fn replays_thinking_fragments() {
+        let exchange = fixture("deepseek/thinking_fragments.json");
+
+        // Lines and characters split across network chunks must come out whole
+        for chunk_size in [0, 1, 2, 3, 5, 8, 13, 64] {
+            let mut answer = String::new();
+            let parser = read_body(exchange.replay(chunk_size), |delta| {
+                if let Delta::Answer(text) = delta {
+                    answer.push_str(&text);
+                }
+            })
+            .await
+            .unwrap();
+
+            assert_eq!(parser.thinking, "嗯，用户在打招呼。");
+            assert_eq!(parser.content, "你好！有什么可以帮你的？");
+            assert_eq!(answer, parser.content);
+            assert_eq!(parser.message_id, "2");
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_search_results_and_content() {
+        let exchange = fixture("deepseek/search_content.json");
+
+        let parser = read_body(exchange.replay(0), |_| {}).await.unwrap();
+
+        assert_eq!(parser.content, "Rust 是一门系统编程语言。");
+        assert!(parser.thinking.is_empty());
+        assert_eq!(parser.message_id, "4");
+        assert_eq!(parser.search_results.len(), 1);
+        assert_eq!(parser.search_results[0].url, "https://www.rust-lang.org/");
+    }
+}
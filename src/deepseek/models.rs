@@ -22,6 +22,24 @@ pub struct DeepSeekChatRequest {
 pub struct DeepSeekResponse {
     pub response: Option<String>,
     pub extra_data: ExtraData,
+    #[serde(default)]
+    pub thinking_content: Option<String>, // Reasoning trace if thinking was enabled
+    #[serde(default)]
+    pub search_results: Option<Vec<SearchResult>>, // Pages cited if search was enabled
+}
+
+// A web page DeepSeek consulted while searching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub snippet: Option<String>,
+    #[serde(default)]
+    pub site_name: Option<String>,
+    #[serde(default)]
+    pub cite_index: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,22 +58,12 @@ impl DeepSeekHash {
             rand::random::<f64>()
         })?;
 
-        // Define the `wbg_log` function that `wasm-bindgen` expects
+        // Define the `wbg_log` function that `wasm-bindgen` expects; the module's debug
+        // output is dropped rather than written to the embedding app's stdout
         linker.func_wrap(
             "wbg",
             "__wbg_log_0400000000000000",
-            |mut caller: Caller<'_, ()>, ptr: i32, len: i32| {
-                let memory = match caller.get_export("memory") {
-                    Some(wasmtime::Extern::Memory(mem)) => mem,
-                    _ => panic!("failed to find host memory"),
-                };
-                let data = memory
-                    .data(&caller)
-                    .get(ptr as usize..ptr as usize + len as usize)
-                    .unwrap();
-                let s = std::str::from_utf8(data).unwrap();
-                println!("WASM Log: {}", s);
-            },
+            |_caller: Caller<'_, ()>, _ptr: i32, _len: i32| {},
         )?;
 
         let mut store = Store::new(&engine, ());
@@ -0,0 +1,95 @@
+use std::io::Write;
+
+/// Progress and streaming events emitted by the clients.
+///
+/// Library code never writes to stdout itself; it reports what it is doing
+/// through an [`EventSink`]. Clients default to [`NoopSink`], use
+/// [`ConsoleSink`] to get the old console echo back.
+#[derive(Debug, Clone)]
+pub enum ClientEvent {
+    /// The completion moved to another phase; deep research reports each of its steps this way
+    PhaseChanged { phase: String },
+    /// A piece of the model's reasoning
+    ThinkingDelta(String),
+    /// A piece of the answer text
+    AnswerDelta(String),
+    /// The streamed answer is complete
+    AnswerFinished,
+    /// A video generation task was accepted upstream
+    VideoTaskStarted { task_id: String },
+    /// The video task was polled
+    VideoProgress(crate::qwen::models::VideoProgress),
+    /// The video task finished successfully
+    VideoCompleted,
+    /// A media download is starting
+    DownloadStarted { path: String },
+    /// A media download was written to disk
+    DownloadFinished { path: String, bytes: u64 },
+}
+
+/// Receiver for [`ClientEvent`]s
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &ClientEvent);
+}
+
+/// Discards every event
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopSink;
+
+impl EventSink for NoopSink {
+    fn emit(&self, _event: &ClientEvent) {}
+}
+
+/// Echoes deltas and progress to stdout, the way the clients used to
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConsoleSink;
+
+impl EventSink for ConsoleSink {
+    fn emit(&self, event: &ClientEvent) {
+        let mut stdout = std::io::stdout();
+
+        match event {
+            // The answer needs no header, it is what gets printed anyway
+            ClientEvent::PhaseChanged { phase } if phase == "answer" => {}
+            ClientEvent::PhaseChanged { phase } => println!("\n▶ {}", phase),
+            ClientEvent::ThinkingDelta(text) | ClientEvent::AnswerDelta(text) => {
+                print!("{}", text);
+                stdout.flush().ok();
+            }
+            ClientEvent::AnswerFinished => println!(),
+            ClientEvent::VideoTaskStarted { task_id } => {
+                println!("🎬 Video generation started, task_id: {}", task_id);
+            }
+            ClientEvent::VideoProgress(progress) => {
+                if progress.status == "running" {
+                    match progress.remaining {
+                        Some(remaining) => print!(
+                            "\r⏳ {}s elapsed, about {}s left   ",
+                            progress.elapsed.as_secs(),
+                            remaining.as_secs()
+                        ),
+                        None => print!("\r⏳ {}s elapsed   ", progress.elapsed.as_secs()),
+                    }
+                    stdout.flush().ok();
+                }
+            }
+            ClientEvent::VideoCompleted => println!("\n✅ Video generation completed!"),
+            ClientEvent::DownloadStarted { path } => {
+                println!("⬇️  Downloading media to: {}", path);
+            }
+            ClientEvent::DownloadFinished { bytes, .. } => {
+                println!("✅ Downloaded successfully: {} bytes", bytes);
+            }
+        }
+    }
+}
+
+/// Any closure taking a `&ClientEvent` can be used as a sink
+impl<F> EventSink for F
+where
+    F: Fn(&ClientEvent) + Send + Sync,
+{
+    fn emit(&self, event: &ClientEvent) {
+        self(event)
+    }
+}
@@ -1,8 +1,11 @@
+use super::stream::parse_conversation;
 use crate::grok::*;
+use crate::recording::{Exchange, RecordedRequest, RecordedResponse, Recorder};
 use base64::{engine::general_purpose, Engine as _};
+use rquest::header::{HeaderMap, HeaderValue};
 use rquest::Client;
 use rquest_util::Emulation;
-use serde_json::{json, Value};
+use serde_json::json;
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -24,6 +27,11 @@ pub struct Grok {
     baggage: String,
     sentry_trace: String,
     cookies: HashMap<String, String>,
+    recorder: Recorder,
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|e| GrokError::HttpError(e.to_string()))
 }
 
 impl Grok {
@@ -67,9 +75,17 @@ impl Grok {
             baggage: String::new(),
             sentry_trace: String::new(),
             cookies: HashMap::new(),
+            recorder: Recorder::from_env(),
         })
     }
 
+    /// Write every conversation response to `dir` as a fixture, see [`crate::recording`].
+    /// Defaults to the directory in `REVERSE_API_RECORD_DIR`, if set.
+    pub fn record_to(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.recorder = Recorder::to_dir(dir);
+        self
+    }
+
     async fn load(&mut self, extra_data: Option<&ExtraData>) -> Result<()> {
         if let Some(data) = extra_data {
             self.cookies = data.cookies.clone();
@@ -221,10 +237,6 @@ impl Grok {
                                     .encode(self.keys.get("privateKey").unwrap());
                                 self.challenge_dict =
                                     Some(Anon::sign_challenge(&challenge_bytes, &private_key_b64)?);
-                                Logger::success(&format!(
-                                    "Solved Challenge: {:?}",
-                                    self.challenge_dict
-                                ));
                             }
                         }
                     }
@@ -387,104 +399,50 @@ impl Grok {
             &Uuid::new_v4().simple().to_string()[..16]
         );
 
+        let mut headers = HeaderMap::new();
+        headers.insert("accept", HeaderValue::from_static("*/*"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        headers.insert("baggage", header_value(&self.baggage)?);
+        headers.insert("sentry-trace", header_value(&sentry_trace)?);
+        headers.insert("x-statsig-id", header_value(&xsid)?);
+        headers.insert(
+            "x-xai-request-id",
+            header_value(&Uuid::new_v4().to_string())?,
+        );
+        let recorded =
+            RecordedRequest::new("POST", &url, &headers, Some(conversation_data.clone()));
+
         let response = self
             .client
             .post(&url)
-            .header("accept", "*/*")
-            .header("content-type", "application/json")
-            .header("baggage", &self.baggage)
-            .header("sentry-trace", sentry_trace)
-            .header("x-statsig-id", xsid)
-            .header("x-xai-request-id", Uuid::new_v4().to_string())
+            .headers(headers)
             .json(&conversation_data)
             .send()
             .await?;
 
+        let head = RecordedResponse::head(response.status().as_u16(), response.headers());
         let text = response.text().await?;
 
-        if text.contains("rejected by anti-bot rules") {
-            return Err(GrokError::AntiBotRejection);
-        }
-
-        if !text.contains("modelResponse") {
-            return Err(GrokError::Other(format!("Unexpected response: {}", text)));
-        }
-
-        let mut full_response: Option<String> = None;
-        let mut stream_response = Vec::new();
-        let mut conv_id = conversation_id;
-        let mut parent_response_id: Option<String> = None;
-        let mut image_urls: Option<Vec<String>> = None;
-
-        for line in text.lines() {
-            if let Ok(data) = serde_json::from_str::<Value>(line) {
-                // Extract token
-                if let Some(token) = data["result"]["response"]["token"].as_str() {
-                    stream_response.push(token.to_string());
-                } else if let Some(token) = data["result"]["token"].as_str() {
-                    stream_response.push(token.to_string());
-                }
-
-                // Extract full response
-                if full_response.is_none() {
-                    if let Some(msg) =
-                        data["result"]["response"]["modelResponse"]["message"].as_str()
-                    {
-                        full_response = Some(msg.to_string());
-                    } else if let Some(msg) = data["result"]["modelResponse"]["message"].as_str() {
-                        full_response = Some(msg.to_string());
-                    }
-                }
-
-                // Extract conversation ID
-                if conv_id.is_none() {
-                    if let Some(id) = data["result"]["conversation"]["conversationId"].as_str() {
-                        conv_id = Some(id.to_string());
-                    }
-                }
-
-                // Extract parent response ID
-                if parent_response_id.is_none() {
-                    if let Some(id) =
-                        data["result"]["response"]["modelResponse"]["responseId"].as_str()
-                    {
-                        parent_response_id = Some(id.to_string());
-                    } else if let Some(id) = data["result"]["modelResponse"]["responseId"].as_str()
-                    {
-                        parent_response_id = Some(id.to_string());
-                    }
-                }
+        let _ = self.recorder.save(
+            "grok-conversation",
+            &Exchange {
+                request: recorded,
+                response: RecordedResponse {
+                    body: text.clone(),
+                    ..head
+                },
+            },
+        );
 
-                // Extract image URLs
-                if image_urls.is_none() {
-                    if let Some(urls) =
-                        data["result"]["response"]["modelResponse"]["generatedImageUrls"].as_array()
-                    {
-                        image_urls = Some(
-                            urls.iter()
-                                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                                .collect(),
-                        );
-                    } else if let Some(urls) =
-                        data["result"]["modelResponse"]["generatedImageUrls"].as_array()
-                    {
-                        image_urls = Some(
-                            urls.iter()
-                                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                                .collect(),
-                        );
-                    }
-                }
-            }
-        }
+        let parsed = parse_conversation(&text, conversation_id)?;
 
         let private_key_b64 =
             general_purpose::STANDARD.encode(self.keys.get("privateKey").unwrap());
 
         Ok(GrokResponse {
-            response: full_response,
-            stream_response,
-            images: image_urls,
+            response: parsed.response,
+            stream_response: parsed.stream_response,
+            images: parsed.images,
             extra_data: ExtraData {
                 anon_user: self.anon_user.clone().unwrap(),
                 cookies: self.cookies.clone(),
@@ -492,8 +450,8 @@ impl Grok {
                 xsid_script: self.xsid_script.clone(),
                 baggage: self.baggage.clone(),
                 sentry_trace: self.sentry_trace.clone(),
-                conversation_id: conv_id,
-                parent_response_id,
+                conversation_id: parsed.conversation_id,
+                parent_response_id: parsed.parent_response_id,
                 private_key: private_key_b64,
             },
         })
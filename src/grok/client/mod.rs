@@ -1,3 +1,4 @@
 pub mod grok;
+mod stream;
 
 pub use grok::Grok;
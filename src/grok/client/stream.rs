@@ -0,0 +1,143 @@
+use crate::grok::{GrokError, Result};
+use serde_json::Value;
+
+/// What a conversation response body says, before it is combined with the session state
+#[derive(Debug, Default)]
+pub(crate) struct ParsedConversation {
+    pub response: Option<String>,
+    pub stream_response: Vec<String>,
+    pub conversation_id: Option<String>,
+    pub parent_response_id: Option<String>,
+    pub images: Option<Vec<String>>,
+}
+
+/// Parse the newline-delimited JSON body of a conversation request.
+///
+/// New conversations nest each object under `result.response`, follow-ups put it
+/// directly under `result`; both are accepted. `conversation_id` is kept when given.
+pub(crate) fn parse_conversation(
+    text: &str,
+    conversation_id: Option<String>,
+) -> Result<ParsedConversation> {
+    if text.contains("rejected by anti-bot rules") {
+        return Err(GrokError::AntiBotRejection);
+    }
+
+    if !text.contains("modelResponse") {
+        return Err(GrokError::Other(format!("Unexpected response: {}", text)));
+    }
+
+    let mut parsed = ParsedConversation {
+        conversation_id,
+        ..Default::default()
+    };
+
+    for line in text.lines() {
+        if let Ok(data) = serde_json::from_str::<Value>(line) {
+            // Extract token
+            if let Some(token) = data["result"]["response"]["token"].as_str() {
+                parsed.stream_response.push(token.to_string());
+            } else if let Some(token) = data["result"]["token"].as_str() {
+                parsed.stream_response.push(token.to_string());
+            }
+
+            let model_response = if data["result"]["response"]["modelResponse"].is_object() {
+                &data["result"]["response"]["modelResponse"]
+            } else {
+                &data["result"]["modelResponse"]
+            };
+
+            // Extract full response
+            if parsed.response.is_none() {
+                if let Some(msg) = model_response["message"].as_str() {
+                    parsed.response = Some(msg.to_string());
+                }
+            }
+
+            // Extract conversation ID
+            if parsed.conversation_id.is_none() {
+                if let Some(id) = data["result"]["conversation"]["conversationId"].as_str() {
+                    parsed.conversation_id = Some(id.to_string());
+                }
+            }
+
+            // Extract parent response ID
+            if parsed.parent_response_id.is_none() {
+                if let Some(id) = model_response["responseId"].as_str() {
+                    parsed.parent_response_id = Some(id.to_string());
+                }
+            }
+
+            // Extract image URLs
+            if parsed.images.is_none() {
+                if let Some(urls) = model_response["generatedImageUrls"].as_array() {
+                    parsed.images = Some(
+                        urls.iter()
+                            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                            .collect(),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::fixture;
+
+    #[test]
+    fn replays_a_new_conversation() {
+        let exchange = fixture("grok/new_conversation.json");
+
+        let parsed = parse_conversation(&exchange.response.body, None).unwrap();
+
+        assert_eq!(
+            parsed.stream_response.concat(),
+            "Hello! How can I help you today?"
+        );
+        assert_eq!(
+            parsed.response.as_deref(),
+            Some("Hello! How can I help you today?")
+        );
+        assert_eq!(
+            parsed.conversation_id.as_deref(),
+            Some("3f1c2a9e-5b7d-4e8a-9c21-7d0b6e4f8a13")
+        );
+        assert_eq!(
+            parsed.parent_response_id.as_deref(),
+            Some("b6d1e0c4-2f8a-4c3b-9e57-1a2d3c4e5f60")
+        );
+        assert_eq!(parsed.images, Some(vec![]));
+    }
+
+    #[test]
+    fn replays_a_follow_up() {
+        let exchange = fixture("grok/follow_up.json");
+
+        let parsed =
+            parse_conversation(&exchange.response.body, Some("known-conversation".into())).unwrap();
+
+        assert_eq!(parsed.stream_response.concat(), "Here you go.");
+        assert_eq!(
+            parsed.conversation_id.as_deref(),
+            Some("known-conversation")
+        );
+        assert_eq!(
+            parsed.images,
+            Some(vec!["users/0d9c/generated/7a1e/image.jpg".to_string()])
+        );
+    }
+
+    #[test]
+    fn anti_bot_rejection_is_reported() {
+        let result = parse_conversation(
+            r#"{"error":{"code":7,"message":"Request rejected by anti-bot rules."}}"#,
+            None,
+        );
+        assert!(matches!(result, Err(GrokError::AntiBotRejection)));
+    }
+}
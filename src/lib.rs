@@ -1,13 +1,31 @@
 pub mod chatgpt;
 pub mod deepseek;
+pub mod events;
 pub mod grok;
+pub mod provider;
 pub mod qwen;
+pub mod recording;
+pub mod sse;
 
 pub use chatgpt::{ChatGptClient, ChatGptError};
 pub use deepseek::client::deepseek::DeepSeekClient;
 pub use deepseek::error::{DeepSeekError, Result as DeepSeekResult};
-pub use deepseek::models::{DeepSeekResponse, ExtraData as DeepSeekExtraData};
+pub use deepseek::models::{
+    DeepSeekResponse, ExtraData as DeepSeekExtraData, SearchResult as DeepSeekSearchResult,
+};
+pub use events::{ClientEvent, ConsoleSink, EventSink, NoopSink};
 pub use grok::{ExtraData, Grok, GrokError, GrokResponse, Logger, Result};
+pub use provider::{
+    ChatMessage, ChatProvider, ProviderError, ProviderKind, ProviderRegistry, ProviderRequest,
+    ProviderResponse,
+};
+pub use qwen::client::builder::QwenClientBuilder;
+pub use qwen::client::modules::file_type::FileKind;
+pub use qwen::client::modules::streaming::QwenEventStream;
 pub use qwen::client::qwen::QwenClient;
+pub use qwen::client::request::QwenRequestBuilder;
 pub use qwen::error::{QwenError, Result as QwenResult};
-pub use qwen::models::{ExtraData as QwenExtraData, QwenResponse};
+pub use qwen::models::{
+    ChatRequest, ChatType, ExtraData as QwenExtraData, QwenEvent, QwenResponse, UploadLimits,
+    VideoPollConfig, VideoProgress, VideoRequest, VideoTask,
+};
@@ -0,0 +1,55 @@
+use super::{
+    continuation_for, reject_unsupported_options, ChatProvider, Continuation, ProviderError,
+    ProviderKind, ProviderRequest, ProviderResponse,
+};
+use crate::chatgpt::ChatGptClient;
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+/// Wraps one [`ChatGptClient`] session.
+///
+/// The client keeps the conversation state itself, so requests are serialized and a
+/// [`Continuation::ChatGpt`] means "continue the conversation this client is holding".
+pub struct ChatGptProvider {
+    client: Mutex<ChatGptClient>,
+}
+
+impl ChatGptProvider {
+    pub async fn new(proxy: Option<&str>) -> crate::chatgpt::Result<Self> {
+        Ok(Self::from_client(ChatGptClient::new(proxy).await?))
+    }
+
+    pub fn from_client(client: ChatGptClient) -> Self {
+        Self {
+            client: Mutex::new(client),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for ChatGptProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::ChatGpt
+    }
+
+    async fn chat(&self, request: &ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+        reject_unsupported_options(request, ProviderKind::ChatGpt, false, false)?;
+
+        let prompt = request.prompt()?;
+        let continued = continuation_for(request, ProviderKind::ChatGpt)?.is_some();
+
+        let mut client = self.client.lock().await;
+        let content = if continued {
+            client.hold_conversation(&prompt, false).await?
+        } else {
+            client.start_conversation(&prompt).await?
+        };
+
+        Ok(ProviderResponse {
+            content,
+            reasoning: None,
+            citations: vec![],
+            continuation: Some(Continuation::ChatGpt),
+        })
+    }
+}
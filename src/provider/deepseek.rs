@@ -0,0 +1,43 @@
+use super::{
+    continuation_for, ChatProvider, Citation, Continuation, ProviderError, ProviderKind,
+    ProviderRequest, ProviderResponse,
+};
+use crate::deepseek::client::deepseek::DeepSeekClient;
+use async_trait::async_trait;
+
+#[async_trait]
+impl ChatProvider for DeepSeekClient {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::DeepSeek
+    }
+
+    async fn chat(&self, request: &ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+        let prompt = request.prompt()?;
+        let extra_data = match continuation_for(request, ProviderKind::DeepSeek)? {
+            Some(Continuation::DeepSeek(extra_data)) => Some(extra_data),
+            _ => None,
+        };
+
+        // `deepseek-reasoner` is DeepSeek-R1, which always thinks
+        let thinking = request.options.enable_thinking || request.model == "deepseek-reasoner";
+        let response = self
+            .start_convo_with_options(&prompt, extra_data, request.options.enable_search, thinking)
+            .await?;
+
+        Ok(ProviderResponse {
+            content: response.response.unwrap_or_default(),
+            reasoning: response.thinking_content,
+            citations: response
+                .search_results
+                .unwrap_or_default()
+                .into_iter()
+                .map(|result| Citation {
+                    url: result.url,
+                    title: result.title,
+                    snippet: result.snippet,
+                })
+                .collect(),
+            continuation: Some(Continuation::DeepSeek(response.extra_data)),
+        })
+    }
+}
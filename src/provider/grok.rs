@@ -0,0 +1,48 @@
+use super::{
+    continuation_for, reject_unsupported_options, ChatProvider, Continuation, ProviderError,
+    ProviderKind, ProviderRequest, ProviderResponse,
+};
+use crate::grok::Grok;
+use async_trait::async_trait;
+
+/// [`Grok`] is bound to one model and mutated while chatting, so a fresh client is
+/// built per request; the conversation itself travels in the continuation.
+#[derive(Debug, Clone, Default)]
+pub struct GrokProvider {
+    proxy: Option<String>,
+}
+
+impl GrokProvider {
+    pub fn new(proxy: Option<&str>) -> Self {
+        Self {
+            proxy: proxy.map(|p| p.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatProvider for GrokProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Grok
+    }
+
+    async fn chat(&self, request: &ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+        reject_unsupported_options(request, ProviderKind::Grok, false, false)?;
+
+        let prompt = request.prompt()?;
+        let extra_data = match continuation_for(request, ProviderKind::Grok)? {
+            Some(Continuation::Grok(extra_data)) => Some(extra_data),
+            _ => None,
+        };
+
+        let mut grok = Grok::new(&request.model, self.proxy.as_deref())?;
+        let response = grok.start_convo(&prompt, extra_data).await?;
+
+        Ok(ProviderResponse {
+            content: response.response.unwrap_or_default(),
+            reasoning: None,
+            citations: vec![],
+            continuation: Some(Continuation::Grok(response.extra_data)),
+        })
+    }
+}
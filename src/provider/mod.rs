@@ -0,0 +1,385 @@
+//! One chat interface over every client in the crate.
+//!
+//! Each client keeps its own API; [`ChatProvider`] adapts them to a shared
+//! [`ProviderRequest`] / [`ProviderResponse`] pair so callers can pick a
+//! backend from the model id alone:
+//!
+//! ```no_run
+//! use reverse_api::provider::{ProviderRegistry, ProviderRequest};
+//! use reverse_api::QwenClient;
+//! use std::sync::Arc;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut providers = ProviderRegistry::new();
+//! providers.register(Arc::new(QwenClient::with_token("token".to_string())?));
+//!
+//! let response = providers
+//!     .chat(&ProviderRequest::user("qwen3-max", "Hello!"))
+//!     .await?;
+//! println!("{}", response.content);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod chatgpt;
+pub mod deepseek;
+pub mod grok;
+pub mod qwen;
+
+pub use chatgpt::ChatGptProvider;
+pub use grok::GrokProvider;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Backend family, derived from the model id prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Qwen,
+    DeepSeek,
+    Grok,
+    ChatGpt,
+}
+
+impl ProviderKind {
+    /// `qwen*`, `deepseek*`, `grok*`, and `gpt*` / `chatgpt*` model ids
+    pub fn from_model(model: &str) -> Option<Self> {
+        let model = model.to_ascii_lowercase();
+        if model.starts_with("qwen") {
+            Some(ProviderKind::Qwen)
+        } else if model.starts_with("deepseek") {
+            Some(ProviderKind::DeepSeek)
+        } else if model.starts_with("grok") {
+            Some(ProviderKind::Grok)
+        } else if model.starts_with("gpt") || model.starts_with("chatgpt") {
+            Some(ProviderKind::ChatGpt)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKind::Qwen => "qwen",
+            ProviderKind::DeepSeek => "deepseek",
+            ProviderKind::Grok => "grok",
+            ProviderKind::ChatGpt => "chatgpt",
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// `system`, `user` or `assistant`
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: role.into(),
+            content: content.into(),
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new("system", content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new("user", content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new("assistant", content)
+    }
+}
+
+/// Per-request switches; providers reject the ones they cannot honor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatOptions {
+    #[serde(default)]
+    pub enable_search: bool,
+    #[serde(default)]
+    pub enable_thinking: bool,
+    /// Continue the conversation a previous response belongs to
+    #[serde(default)]
+    pub continuation: Option<Continuation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderRequest {
+    pub messages: Vec<ChatMessage>,
+    pub model: String,
+    #[serde(default)]
+    pub options: ChatOptions,
+}
+
+impl ProviderRequest {
+    pub fn new(model: impl Into<String>, messages: Vec<ChatMessage>) -> Self {
+        Self {
+            messages,
+            model: model.into(),
+            options: ChatOptions::default(),
+        }
+    }
+
+    /// A single user message
+    pub fn user(model: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(model, vec![ChatMessage::user(message)])
+    }
+
+    pub fn with_options(mut self, options: ChatOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_continuation(mut self, continuation: Continuation) -> Self {
+        self.options.continuation = Some(continuation);
+        self
+    }
+
+    /// The text to send upstream.
+    ///
+    /// A continued conversation already has its history on the provider side, so only
+    /// the last user message is sent. A new conversation gets the whole transcript.
+    pub fn prompt(&self) -> Result<String, ProviderError> {
+        let last_user = self
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .ok_or_else(|| ProviderError::InvalidRequest("No user message".to_string()))?;
+
+        if self.options.continuation.is_some() {
+            return Ok(last_user.content.clone());
+        }
+
+        render_transcript(&self.messages)
+    }
+}
+
+/// Flatten a message list into one prompt; a lone message is sent as-is
+pub fn render_transcript(messages: &[ChatMessage]) -> Result<String, ProviderError> {
+    let mut prompt = String::new();
+
+    for message in messages {
+        let label = match message.role.as_str() {
+            "system" => "System",
+            "user" => "User",
+            "assistant" => "Assistant",
+            other => {
+                return Err(ProviderError::InvalidRequest(format!(
+                    "Unsupported message role: {}",
+                    other
+                )))
+            }
+        };
+        prompt.push_str(&format!("{}: {}\n\n", label, message.content));
+    }
+
+    if let [only] = messages {
+        return Ok(only.content.clone());
+    }
+
+    prompt.push_str("Assistant:");
+    Ok(prompt)
+}
+
+/// A source the answer was grounded on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Citation {
+    pub url: String,
+    pub title: String,
+    #[serde(default)]
+    pub snippet: Option<String>,
+}
+
+/// Provider-specific state needed to continue a conversation.
+///
+/// Serializable so it can be stored alongside a thread and passed back in
+/// [`ChatOptions::continuation`] on the next turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", content = "data", rename_all = "lowercase")]
+pub enum Continuation {
+    Qwen(crate::qwen::models::ExtraData),
+    DeepSeek(crate::deepseek::models::ExtraData),
+    Grok(crate::grok::ExtraData),
+    /// ChatGPT keeps the conversation inside the client, so there is nothing to carry
+    ChatGpt,
+}
+
+impl Continuation {
+    pub fn kind(&self) -> ProviderKind {
+        match self {
+            Continuation::Qwen(_) => ProviderKind::Qwen,
+            Continuation::DeepSeek(_) => ProviderKind::DeepSeek,
+            Continuation::Grok(_) => ProviderKind::Grok,
+            Continuation::ChatGpt => ProviderKind::ChatGpt,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderResponse {
+    pub content: String,
+    /// Reasoning trace, when thinking was enabled and the provider exposes it
+    #[serde(default)]
+    pub reasoning: Option<String>,
+    #[serde(default)]
+    pub citations: Vec<Citation>,
+    /// Pass back in the next request to continue this conversation
+    #[serde(default)]
+    pub continuation: Option<Continuation>,
+}
+
+#[derive(Debug, Error)]
+pub enum ProviderError {
+    #[error("Unsupported model: {0}")]
+    UnsupportedModel(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Qwen error: {0}")]
+    Qwen(#[from] crate::qwen::error::QwenError),
+
+    #[error("DeepSeek error: {0}")]
+    DeepSeek(#[from] crate::deepseek::error::DeepSeekError),
+
+    #[error("Grok error: {0}")]
+    Grok(#[from] crate::grok::GrokError),
+
+    #[error("ChatGPT error: {0}")]
+    ChatGpt(#[from] crate::chatgpt::ChatGptError),
+}
+
+#[async_trait]
+pub trait ChatProvider: Send + Sync {
+    fn kind(&self) -> ProviderKind;
+
+    async fn chat(&self, request: &ProviderRequest) -> Result<ProviderResponse, ProviderError>;
+}
+
+/// Routes requests to the provider registered for the model's prefix
+#[derive(Default, Clone)]
+pub struct ProviderRegistry {
+    providers: Vec<Arc<dyn ChatProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a provider, replacing any earlier one of the same kind
+    pub fn register(&mut self, provider: Arc<dyn ChatProvider>) {
+        self.providers.retain(|p| p.kind() != provider.kind());
+        self.providers.push(provider);
+    }
+
+    pub fn get(&self, kind: ProviderKind) -> Option<&Arc<dyn ChatProvider>> {
+        self.providers.iter().find(|p| p.kind() == kind)
+    }
+
+    /// Kinds with a registered provider, in registration order
+    pub fn kinds(&self) -> Vec<ProviderKind> {
+        self.providers.iter().map(|p| p.kind()).collect()
+    }
+
+    pub fn for_model(&self, model: &str) -> Result<&Arc<dyn ChatProvider>, ProviderError> {
+        ProviderKind::from_model(model)
+            .and_then(|kind| self.get(kind))
+            .ok_or_else(|| ProviderError::UnsupportedModel(model.to_string()))
+    }
+
+    pub async fn chat(&self, request: &ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+        self.for_model(&request.model)?.chat(request).await
+    }
+}
+
+/// The continuation to hand to `kind`, rejecting one that belongs to another provider
+pub(crate) fn continuation_for(
+    request: &ProviderRequest,
+    kind: ProviderKind,
+) -> Result<Option<&Continuation>, ProviderError> {
+    match &request.options.continuation {
+        Some(c) if c.kind() != kind => Err(ProviderError::InvalidRequest(format!(
+            "Cannot continue a {} conversation with {}",
+            c.kind(),
+            kind
+        ))),
+        other => Ok(other.as_ref()),
+    }
+}
+
+/// Fail for options a provider has no way to honor instead of silently ignoring them
+pub(crate) fn reject_unsupported_options(
+    request: &ProviderRequest,
+    kind: ProviderKind,
+    search: bool,
+    thinking: bool,
+) -> Result<(), ProviderError> {
+    if request.options.enable_search && !search {
+        return Err(ProviderError::InvalidRequest(format!(
+            "{} does not support search",
+            kind
+        )));
+    }
+    if request.options.enable_thinking && !thinking {
+        return Err(ProviderError::InvalidRequest(format!(
+            "{} does not support thinking",
+            kind
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_prefix_picks_provider() {
+        assert_eq!(
+            ProviderKind::from_model("qwen3-max"),
+            Some(ProviderKind::Qwen)
+        );
+        assert_eq!(
+            ProviderKind::from_model("deepseek-reasoner"),
+            Some(ProviderKind::DeepSeek)
+        );
+        assert_eq!(ProviderKind::from_model("grok-3"), Some(ProviderKind::Grok));
+        assert_eq!(
+            ProviderKind::from_model("gpt-4o"),
+            Some(ProviderKind::ChatGpt)
+        );
+        assert_eq!(ProviderKind::from_model("llama-3"), None);
+    }
+
+    #[test]
+    fn continued_conversation_sends_only_last_user_message() {
+        let messages = vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("Hi"),
+            ChatMessage::assistant("Hello!"),
+            ChatMessage::user("What is Rust?"),
+        ];
+        let request = ProviderRequest::new("qwen3-max", messages);
+        assert!(request.prompt().unwrap().starts_with("System: Be brief."));
+
+        let request = request.with_continuation(Continuation::ChatGpt);
+        assert_eq!(request.prompt().unwrap(), "What is Rust?");
+    }
+}
@@ -0,0 +1,55 @@
+use super::{
+    continuation_for, ChatProvider, Citation, Continuation, ProviderError, ProviderKind,
+    ProviderRequest, ProviderResponse,
+};
+use crate::qwen::client::qwen::QwenClient;
+use async_trait::async_trait;
+
+#[async_trait]
+impl ChatProvider for QwenClient {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Qwen
+    }
+
+    async fn chat(&self, request: &ProviderRequest) -> Result<ProviderResponse, ProviderError> {
+        let prompt = request.prompt()?;
+        let extra_data = match continuation_for(request, ProviderKind::Qwen)? {
+            Some(Continuation::Qwen(extra_data)) => Some(extra_data),
+            _ => None,
+        };
+
+        let mut builder = self
+            .request(prompt)
+            .model(&request.model)
+            .search(request.options.enable_search)
+            .thinking(request.options.enable_thinking);
+        if let Some(extra_data) = extra_data {
+            builder = builder.continue_from(extra_data.clone());
+        }
+        let response = builder.send().await?;
+
+        let continuation = response.chat_id.clone().map(|chat_id| {
+            Continuation::Qwen(crate::qwen::models::ExtraData {
+                chat_id,
+                model_id: request.model.clone(),
+                parent_id: Some(response.response_id.clone()),
+            })
+        });
+
+        Ok(ProviderResponse {
+            content: response.content,
+            reasoning: response.thinking_content,
+            citations: response
+                .web_search_results
+                .unwrap_or_default()
+                .into_iter()
+                .map(|result| Citation {
+                    url: result.url,
+                    title: result.title,
+                    snippet: Some(result.snippet).filter(|s| !s.is_empty()),
+                })
+                .collect(),
+            continuation,
+        })
+    }
+}
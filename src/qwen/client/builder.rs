@@ -0,0 +1,277 @@
+use super::modules::auth::AuthManager;
+use super::modules::constants::{Upstream, BASE_URL};
+use super::qwen::QwenClient;
+use crate::events::{EventSink, NoopSink};
+use crate::qwen::error::{QwenError, Result};
+use crate::qwen::models::{UploadLimits, VideoPollConfig};
+use crate::recording::Recorder;
+use rquest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Clone)]
+enum Credentials {
+    Token(String),
+    Login { email: String, password: String },
+}
+
+/// Builder returned by [`QwenClient::builder`].
+///
+/// Clones of a builder that went through [`share_http_client`](Self::share_http_client)
+/// hand the same connection pool to every client they build. The shared client keeps
+/// no cookies, so one account's session cookies never travel with another's token:
+///
+/// ```no_run
+/// use reverse_api::QwenClient;
+/// use std::time::Duration;
+///
+/// # fn main() -> reverse_api::QwenResult<()> {
+/// let template = QwenClient::builder()
+///     .base_url("http://127.0.0.1:8080")
+///     .timeout(Duration::from_secs(120))
+///     .share_http_client()?;
+///
+/// let first = template.clone().token("token-a").build()?;
+/// let second = template.clone().token("token-b").build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct QwenClientBuilder {
+    credentials: Option<Credentials>,
+    base_url: String,
+    proxy: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    http_client: Option<rquest::Client>,
+    events: Arc<dyn EventSink>,
+    video_poll: VideoPollConfig,
+    upload_limits: UploadLimits,
+    recorder: Recorder,
+}
+
+impl Default for QwenClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QwenClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            credentials: None,
+            base_url: BASE_URL.to_string(),
+            proxy: None,
+            timeout: None,
+            connect_timeout: None,
+            headers: Vec::new(),
+            http_client: None,
+            events: Arc::new(NoopSink),
+            video_poll: VideoPollConfig::default(),
+            upload_limits: UploadLimits::default(),
+            recorder: Recorder::from_env(),
+        }
+    }
+
+    /// Authenticate with an existing token
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Token(token.into()));
+        self
+    }
+
+    /// Sign in with email and password on first use
+    pub fn credentials(mut self, email: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Login {
+            email: email.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Where API requests go, `https://chat.qwen.ai` by default
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Send every request through this proxy, e.g. `http://proxy.corp:3128`
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Limit for a whole request, including reading a streamed response
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Header added to every API request, replacing the default header of the same name
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Use `client` instead of building one.
+    /// Proxy and timeouts then have to be configured on `client` itself.
+    pub fn http_client(mut self, client: rquest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Build the HTTP client now so clones of this builder share its connection pool.
+    /// Unlike the client each `build` creates on its own, it has no cookie store.
+    pub fn share_http_client(mut self) -> Result<Self> {
+        if self.http_client.is_none() {
+            self.http_client = Some(self.build_http_client(false)?);
+            self.proxy = None;
+            self.timeout = None;
+            self.connect_timeout = None;
+        }
+        Ok(self)
+    }
+
+    pub fn event_sink(mut self, events: Arc<dyn EventSink>) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn video_poll_config(mut self, video_poll: VideoPollConfig) -> Self {
+        self.video_poll = video_poll;
+        self
+    }
+
+    pub fn upload_limits(mut self, limits: UploadLimits) -> Self {
+        self.upload_limits = limits;
+        self
+    }
+
+    /// Write every streamed completion to `dir` as a fixture, see [`crate::recording`].
+    /// Defaults to the directory in `REVERSE_API_RECORD_DIR`, if set.
+    pub fn record_to(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.recorder = Recorder::to_dir(dir);
+        self
+    }
+
+    pub fn build(self) -> Result<QwenClient> {
+        let client = match &self.http_client {
+            Some(client) => {
+                if self.proxy.is_some() || self.timeout.is_some() || self.connect_timeout.is_some()
+                {
+                    return Err(QwenError::InvalidRequest(
+                        "Proxy and timeouts cannot be combined with an injected HTTP client"
+                            .to_string(),
+                    ));
+                }
+                client.clone()
+            }
+            None => self.build_http_client(true)?,
+        };
+
+        let base_url = url::Url::parse(&self.base_url)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or_else(|| {
+                QwenError::InvalidRequest(format!("Invalid base URL: {}", self.base_url))
+            })?;
+        let http = Upstream::new(
+            client,
+            base_url.as_str(),
+            self.extra_headers()?,
+            self.recorder.clone(),
+        );
+
+        let auth = match self.credentials {
+            Some(Credentials::Token(token)) => AuthManager::with_token(token, http.clone()),
+            Some(Credentials::Login { email, password }) => {
+                AuthManager::new(email, password, http.clone())
+            }
+            None => {
+                return Err(QwenError::InvalidRequest(
+                    "A token or email and password is required".to_string(),
+                ))
+            }
+        };
+
+        Ok(QwenClient::from_parts(
+            http,
+            auth,
+            self.events,
+            self.video_poll,
+            self.upload_limits,
+        ))
+    }
+
+    fn build_http_client(&self, cookies: bool) -> Result<rquest::Client> {
+        let mut builder = rquest::Client::builder().cookie_store(cookies);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(rquest::Proxy::all(proxy.as_str())?);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        Ok(builder.build()?)
+    }
+
+    fn extra_headers(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let invalid = || QwenError::InvalidRequest(format!("Invalid header: {}", name));
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+            let value = HeaderValue::from_str(value).map_err(|_| invalid())?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injected_client_is_used_as_is() {
+        let client = QwenClient::builder()
+            .token("token")
+            .base_url("http://127.0.0.1:9000/")
+            .http_client(rquest::Client::new())
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url(), "http://127.0.0.1:9000");
+
+        let conflicting = QwenClient::builder()
+            .token("token")
+            .http_client(rquest::Client::new())
+            .proxy("http://proxy.example.com:3128")
+            .build();
+        assert!(matches!(conflicting, Err(QwenError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        let missing_auth = QwenClient::builder().build();
+        assert!(matches!(missing_auth, Err(QwenError::InvalidRequest(_))));
+
+        let bad_url = QwenClient::builder()
+            .token("token")
+            .base_url("chat.qwen.ai")
+            .build();
+        assert!(matches!(bad_url, Err(QwenError::InvalidRequest(_))));
+
+        let bad_header = QwenClient::builder()
+            .token("token")
+            .header("bad header", "value")
+            .build();
+        assert!(matches!(bad_header, Err(QwenError::InvalidRequest(_))));
+    }
+}
@@ -1,2 +1,4 @@
+pub mod builder;
 pub mod modules;
 pub mod qwen;
+pub mod request;
@@ -1,4 +1,4 @@
-use super::constants::{build_json_headers, BASE_URL};
+use super::constants::Upstream;
 use crate::qwen::error::{QwenError, Result};
 use crate::qwen::models::{AuthResponse, SignInRequest};
 use rquest::header::CONTENT_TYPE;
@@ -8,25 +8,25 @@ use tokio::sync::Mutex;
 pub struct AuthManager {
     email: String,
     password: String,
-    client: rquest::Client,
+    http: Upstream,
     token_cache: Arc<Mutex<Option<String>>>,
 }
 
 impl AuthManager {
-    pub fn new(email: String, password: String, client: rquest::Client) -> Self {
+    pub fn new(email: String, password: String, http: Upstream) -> Self {
         Self {
             email,
             password,
-            client,
+            http,
             token_cache: Arc::new(Mutex::new(None)),
         }
     }
 
-    pub fn with_token(token: String, client: rquest::Client) -> Self {
+    pub fn with_token(token: String, http: Upstream) -> Self {
         Self {
             email: String::new(),
             password: String::new(),
-            client,
+            http,
             token_cache: Arc::new(Mutex::new(Some(token))),
         }
     }
@@ -38,8 +38,8 @@ impl AuthManager {
             return Ok(token.clone());
         }
 
-        let url = format!("{}/api/v1/auths/signin", BASE_URL);
-        let mut headers = build_json_headers(None);
+        let url = self.http.url("/api/v1/auths/signin");
+        let mut headers = self.http.json_headers(None);
         headers.insert(
             CONTENT_TYPE,
             rquest::header::HeaderValue::from_static("application/json"),
@@ -51,6 +51,7 @@ impl AuthManager {
         };
 
         let response = self
+            .http
             .client
             .post(&url)
             .headers(headers)
@@ -59,13 +60,17 @@ impl AuthManager {
             .await?;
 
         let status = response.status();
+        let response_headers = response.headers().clone();
         let response_text = response.text().await?;
 
         if !status.is_success() {
-            return Err(QwenError::ApiError(format!(
-                "Login failed ({}): {}",
-                status, response_text
-            )));
+            // Wrong credentials come back as a plain 400
+            return Err(
+                match QwenError::from_response("Login", status, &response_headers, &response_text) {
+                    QwenError::InvalidRequest(msg) => QwenError::Unauthorized(msg),
+                    e => e,
+                },
+            );
         }
 
         let auth_response: AuthResponse = serde_json::from_str(&response_text)?;
@@ -1,5 +1,5 @@
 use super::auth::AuthManager;
-use super::constants::{build_json_headers, BASE_URL};
+use super::constants::Upstream;
 use crate::qwen::error::{QwenError, Result};
 use crate::qwen::models::{
     ChatConfig, CreateChatRequest, CreateChatResponse, Model, ModelsResponse,
@@ -10,15 +10,15 @@ use tokio::sync::Mutex;
 use uuid::Uuid;
 
 pub struct ChatManager {
-    client: rquest::Client,
+    http: Upstream,
     auth: Arc<AuthManager>,
     chat_cache: Arc<Mutex<HashMap<String, String>>>,
 }
 
 impl ChatManager {
-    pub fn new(client: rquest::Client, auth: Arc<AuthManager>) -> Self {
+    pub fn new(http: Upstream, auth: Arc<AuthManager>) -> Self {
         Self {
-            client,
+            http,
             auth,
             chat_cache: Arc::new(Mutex::new(HashMap::new())),
         }
@@ -26,12 +26,23 @@ impl ChatManager {
 
     pub async fn get_models(&self) -> Result<Vec<Model>> {
         let token = self.auth.get_token().await?;
-        let url = format!("{}/api/models", BASE_URL);
-        let headers = build_json_headers(Some(&token));
+        let url = self.http.url("/api/models");
+        let headers = self.http.json_headers(Some(&token));
 
-        let response = self.client.get(&url).headers(headers).send().await?;
+        let response = self.http.client.get(&url).headers(headers).send().await?;
 
+        let status = response.status();
+        let response_headers = response.headers().clone();
         let response_text = response.text().await?;
+        if !status.is_success() {
+            return Err(QwenError::from_response(
+                "Get models",
+                status,
+                &response_headers,
+                &response_text,
+            ));
+        }
+
         let models_response: ModelsResponse = serde_json::from_str(&response_text)?;
 
         Ok(models_response.data)
@@ -45,8 +56,8 @@ impl ChatManager {
         }
 
         let token = self.auth.get_token().await?;
-        let url = format!("{}/api/v2/chats/new", BASE_URL);
-        let headers = build_json_headers(Some(&token));
+        let url = self.http.url("/api/v2/chats/new");
+        let headers = self.http.json_headers(Some(&token));
 
         let chat_name = format!("Chat {}", &Uuid::new_v4().to_string()[..8]);
         let create_request = CreateChatRequest {
@@ -57,6 +68,7 @@ impl ChatManager {
         };
 
         let response = self
+            .http
             .client
             .post(&url)
             .headers(headers)
@@ -64,14 +76,18 @@ impl ChatManager {
             .send()
             .await?;
 
+        let status = response.status();
+        let response_headers = response.headers().clone();
         let response_text = response.text().await?;
-        let create_response: CreateChatResponse = serde_json::from_str(&response_text)?;
+        let failed =
+            || QwenError::from_response("Create chat", status, &response_headers, &response_text);
+        if !status.is_success() {
+            return Err(failed());
+        }
 
+        let create_response: CreateChatResponse = serde_json::from_str(&response_text)?;
         if !create_response.success {
-            return Err(QwenError::ApiError(format!(
-                "Failed to create chat: {}",
-                response_text
-            )));
+            return Err(failed());
         }
 
         let chat_id = create_response.data.id.clone();
@@ -82,10 +98,10 @@ impl ChatManager {
 
     pub async fn get_user_id(&self) -> Result<String> {
         let token = self.auth.get_token().await?;
-        let url = format!("{}/api/v1/users/user/settings", BASE_URL);
-        let headers = build_json_headers(Some(&token));
+        let url = self.http.url("/api/v1/users/user/settings");
+        let headers = self.http.json_headers(Some(&token));
 
-        let response = self.client.get(&url).headers(headers).send().await?;
+        let response = self.http.client.get(&url).headers(headers).send().await?;
 
         if !response.status().is_success() {
             return Ok(Uuid::new_v4().to_string());
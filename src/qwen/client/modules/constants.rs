@@ -1,3 +1,4 @@
+use crate::recording::Recorder;
 use rquest::header::{HeaderMap, HeaderValue};
 
 pub const BASE_URL: &str = "https://chat.qwen.ai";
@@ -38,3 +39,47 @@ pub fn build_json_headers(with_auth: Option<&str>) -> HeaderMap {
     headers.insert("content-type", HeaderValue::from_static("application/json"));
     headers
 }
+
+/// HTTP client, base URL and extra headers shared by every module of a `QwenClient`
+#[derive(Clone)]
+pub struct Upstream {
+    pub client: rquest::Client,
+    base_url: String,
+    extra_headers: HeaderMap,
+    /// Where streamed completions are recorded, if anywhere
+    pub recorder: Recorder,
+}
+
+impl Upstream {
+    pub fn new(
+        client: rquest::Client,
+        base_url: &str,
+        extra_headers: HeaderMap,
+        recorder: Recorder,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            extra_headers,
+            recorder,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Absolute URL for an API path such as `/api/models`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// JSON headers with the configured extra headers applied on top
+    pub fn json_headers(&self, with_auth: Option<&str>) -> HeaderMap {
+        let mut headers = build_json_headers(with_auth);
+        for (name, value) in &self.extra_headers {
+            headers.insert(name.clone(), value.clone());
+        }
+        headers
+    }
+}
@@ -0,0 +1,317 @@
+use image::ImageFormat;
+use std::path::Path;
+
+/// Bytes of a file needed to recognise its type
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+/// How Qwen sees an uploaded file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileKind {
+    /// `image`, `video`, `audio` or `file`
+    pub filetype: &'static str,
+    /// `vision`, `video`, `audio` or `document`
+    pub file_class: &'static str,
+    /// `image` for pictures, `file` for everything else
+    pub show_type: &'static str,
+    pub content_type: &'static str,
+}
+
+impl FileKind {
+    const fn image(content_type: &'static str) -> Self {
+        Self {
+            filetype: "image",
+            file_class: "vision",
+            show_type: "image",
+            content_type,
+        }
+    }
+
+    const fn video(content_type: &'static str) -> Self {
+        Self {
+            filetype: "video",
+            file_class: "video",
+            show_type: "file",
+            content_type,
+        }
+    }
+
+    const fn audio(content_type: &'static str) -> Self {
+        Self {
+            filetype: "audio",
+            file_class: "audio",
+            show_type: "file",
+            content_type,
+        }
+    }
+
+    const fn document(content_type: &'static str) -> Self {
+        Self {
+            filetype: "file",
+            file_class: "document",
+            show_type: "file",
+            content_type,
+        }
+    }
+
+    /// Classify a file from its first bytes (see [`SNIFF_LEN`]), falling back to the
+    /// extension of `filename` when the content has no recognisable signature.
+    /// `None` means Qwen does not accept the file.
+    pub fn detect(filename: &str, head: &[u8]) -> Option<Self> {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        if let Some(kind) = Self::sniff(head, &extension) {
+            return Some(kind);
+        }
+
+        let kind = Self::from_extension(&extension)?;
+        // Text formats have no signature; binary content under a text extension is rejected
+        let is_text_format =
+            kind.content_type.starts_with("text/") || kind.content_type == "application/json";
+        if is_text_format && !is_text(head) {
+            return None;
+        }
+        Some(kind)
+    }
+
+    fn sniff(head: &[u8], extension: &str) -> Option<Self> {
+        if head.starts_with(b"%PDF-") {
+            return Some(Self::document("application/pdf"));
+        }
+        if head.starts_with(b"PK\x03\x04") {
+            return Self::office(head, extension);
+        }
+        if head.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]) {
+            // Legacy Office files share one container format; only the extension tells them apart
+            return match extension {
+                "xls" => Self::from_extension("xls"),
+                "ppt" => Self::from_extension("ppt"),
+                _ => Some(Self::document("application/msword")),
+            };
+        }
+        if head.len() >= 12 && &head[4..8] == b"ftyp" {
+            return Self::iso_media(&head[8..12]);
+        }
+        if head.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            let is_webm = head.windows(4).any(|w| w == b"webm");
+            return Some(Self::video(if is_webm {
+                "video/webm"
+            } else {
+                "video/x-matroska"
+            }));
+        }
+        if head.len() >= 12 && head.starts_with(b"RIFF") {
+            match &head[8..12] {
+                b"WAVE" => return Some(Self::audio("audio/wav")),
+                b"AVI " => return Some(Self::video("video/x-msvideo")),
+                _ => {}
+            }
+        }
+        if head.starts_with(b"fLaC") {
+            return Some(Self::audio("audio/flac"));
+        }
+        if head.starts_with(b"OggS") {
+            return Some(Self::audio("audio/ogg"));
+        }
+        if head.starts_with(b"ID3")
+            || (head.len() >= 2 && head[0] == 0xFF && head[1] & 0xE6 == 0xE2)
+        {
+            return Some(Self::audio("audio/mpeg"));
+        }
+
+        match image::guess_format(head).ok()? {
+            ImageFormat::Png => Some(Self::image("image/png")),
+            ImageFormat::Jpeg => Some(Self::image("image/jpeg")),
+            ImageFormat::Gif => Some(Self::image("image/gif")),
+            ImageFormat::WebP => Some(Self::image("image/webp")),
+            ImageFormat::Bmp => Some(Self::image("image/bmp")),
+            ImageFormat::Tiff => Some(Self::image("image/tiff")),
+            _ => None,
+        }
+    }
+
+    /// MP4, QuickTime, M4A, HEIC and AVIF all use ISO media boxes; the major brand tells them
+    /// apart. Unlisted brands (including `mif1`/`msf1`, shared by HEIF and AVIF) are left to
+    /// the extension.
+    fn iso_media(brand: &[u8]) -> Option<Self> {
+        match brand {
+            b"avif" | b"avis" => Some(Self::image("image/avif")),
+            b"heic" | b"heix" | b"hevc" | b"hevx" => Some(Self::image("image/heic")),
+            b"qt  " => Some(Self::video("video/quicktime")),
+            b"isom" | b"iso2" | b"iso3" | b"iso4" | b"iso5" | b"iso6" | b"mp41" | b"mp42"
+            | b"avc1" | b"dash" | b"mmp4" | b"MSNV" | b"M4V " | b"M4VH" | b"M4VP" => {
+                Some(Self::video("video/mp4"))
+            }
+            b"M4A " | b"M4B " => Some(Self::audio("audio/mp4")),
+            _ => None,
+        }
+    }
+
+    /// Office Open XML documents are zip files whose entries start with `word/`, `xl/` or `ppt/`
+    fn office(head: &[u8], extension: &str) -> Option<Self> {
+        let has_entry = |prefix: &[u8]| head.windows(prefix.len()).any(|w| w == prefix);
+
+        if has_entry(b"word/") {
+            Self::from_extension("docx")
+        } else if has_entry(b"xl/") {
+            Self::from_extension("xlsx")
+        } else if has_entry(b"ppt/") {
+            Self::from_extension("pptx")
+        } else if matches!(extension, "docx" | "xlsx" | "pptx") {
+            // The entry names may lie beyond the sniffed bytes
+            Self::from_extension(extension)
+        } else {
+            None
+        }
+    }
+
+    fn from_extension(extension: &str) -> Option<Self> {
+        let kind = match extension {
+            "jpg" | "jpeg" => Self::image("image/jpeg"),
+            "png" => Self::image("image/png"),
+            "gif" => Self::image("image/gif"),
+            "webp" => Self::image("image/webp"),
+            "bmp" => Self::image("image/bmp"),
+            "tif" | "tiff" => Self::image("image/tiff"),
+            "heic" | "heif" => Self::image("image/heic"),
+            "avif" => Self::image("image/avif"),
+
+            "mp4" | "m4v" => Self::video("video/mp4"),
+            "avi" => Self::video("video/x-msvideo"),
+            "mov" => Self::video("video/quicktime"),
+            "mkv" => Self::video("video/x-matroska"),
+            "webm" => Self::video("video/webm"),
+
+            "mp3" => Self::audio("audio/mpeg"),
+            "wav" => Self::audio("audio/wav"),
+            "m4a" => Self::audio("audio/mp4"),
+            "flac" => Self::audio("audio/flac"),
+            "ogg" | "oga" | "opus" => Self::audio("audio/ogg"),
+
+            "pdf" => Self::document("application/pdf"),
+            "doc" => Self::document("application/msword"),
+            "docx" => Self::document(
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            ),
+            "xls" => Self::document("application/vnd.ms-excel"),
+            "xlsx" => {
+                Self::document("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+            }
+            "ppt" => Self::document("application/vnd.ms-powerpoint"),
+            "pptx" => Self::document(
+                "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            ),
+
+            "txt" | "log" => Self::document("text/plain"),
+            "md" | "markdown" => Self::document("text/markdown"),
+            "csv" => Self::document("text/csv"),
+            "json" => Self::document("application/json"),
+            "html" | "htm" => Self::document("text/html"),
+            "xml" => Self::document("text/xml"),
+            "yaml" | "yml" | "toml" | "ini" => Self::document("text/plain"),
+
+            // Source code is sent as plain text
+            "rs" | "py" | "js" | "mjs" | "ts" | "tsx" | "jsx" | "java" | "kt" | "go" | "c"
+            | "h" | "cc" | "cpp" | "hpp" | "cs" | "rb" | "php" | "swift" | "scala" | "sh"
+            | "sql" | "css" | "vue" | "lua" | "r" | "dart" => Self::document("text/plain"),
+
+            "" => Self::document("text/plain"),
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+/// UTF-8 without NUL bytes; a multi-byte character cut off at the end of `head` is fine
+fn is_text(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_wins_over_extension() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(FileKind::detect("photo", png).unwrap().file_class, "vision");
+        assert_eq!(
+            FileKind::detect("report.pdf", png).unwrap().content_type,
+            "image/png"
+        );
+
+        let mov = b"\0\0\0\x14ftypqt  \0\0\0\0";
+        assert_eq!(
+            FileKind::detect("clip.mp4", mov).unwrap().content_type,
+            "video/quicktime"
+        );
+        let heic = b"\0\0\0\x18ftypheic\0\0\0\0";
+        assert_eq!(
+            FileKind::detect("IMG_0001", heic).unwrap().file_class,
+            "vision"
+        );
+
+        let pptx = b"PK\x03\x04\x14\0\0\0ppt/slides/slide1.xml";
+        assert_eq!(
+            FileKind::detect("deck.zip", pptx).unwrap().content_type,
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation"
+        );
+    }
+
+    #[test]
+    fn iso_media_brands() {
+        let avif = b"\0\0\0\x1cftypavif\0\0\0\0avifmif1miaf";
+        assert_eq!(
+            FileKind::detect("photo", avif).unwrap().content_type,
+            "image/avif"
+        );
+
+        // `mif1` is used by both HEIF and AVIF, so the extension decides
+        let mif1 = b"\0\0\0\x18ftypmif1\0\0\0\0mif1";
+        assert_eq!(
+            FileKind::detect("photo.avif", mif1).unwrap().content_type,
+            "image/avif"
+        );
+        assert_eq!(
+            FileKind::detect("photo.heif", mif1).unwrap().content_type,
+            "image/heic"
+        );
+
+        let unknown = b"\0\0\0\x14ftypcrx \0\0\0\0";
+        assert!(FileKind::detect("IMG_0001.cr3", unknown).is_none());
+        assert_eq!(
+            FileKind::detect("clip.m4v", b"\0\0\0\x14ftypM4V \0\0\0\0")
+                .unwrap()
+                .content_type,
+            "video/mp4"
+        );
+    }
+
+    #[test]
+    fn text_and_unknown_files() {
+        assert_eq!(
+            FileKind::detect("main.rs", b"fn main() {}")
+                .unwrap()
+                .content_type,
+            "text/plain"
+        );
+        assert_eq!(
+            FileKind::detect("notes", "笔记".as_bytes())
+                .unwrap()
+                .file_class,
+            "document"
+        );
+        assert!(FileKind::detect("main.rs", b"\x7fELF\x02\x01\x01\0").is_none());
+        assert!(FileKind::detect("archive.zip", b"PK\x03\x04\x14\0\0\0data.bin").is_none());
+        assert!(FileKind::detect("setup.exe", b"MZ\x90\0").is_none());
+    }
+}
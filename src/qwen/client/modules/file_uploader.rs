@@ -1,60 +1,68 @@
 use super::auth::AuthManager;
-use super::constants::{build_json_headers, BASE_URL};
+use super::constants::Upstream;
+use super::file_type::{FileKind, SNIFF_LEN};
 use crate::qwen::error::{QwenError, Result};
-use crate::qwen::models::{FileMeta, FileObject, QwenFile, StsTokenRequest, StsTokenResponse};
+use crate::qwen::models::{
+    FileMeta, FileObject, QwenFile, StsTokenRequest, StsTokenResponse, UploadLimits,
+};
+use bytes::Bytes;
+use futures_util::Stream;
 use std::path::Path;
 use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use uuid::Uuid;
 
+/// Bytes read per chunk while streaming an upload
+const UPLOAD_CHUNK_SIZE: usize = 256 * 1024;
+
 pub struct FileUploader {
-    client: rquest::Client,
+    http: Upstream,
     auth: Arc<AuthManager>,
+    limits: UploadLimits,
 }
 
 impl FileUploader {
-    pub fn new(client: rquest::Client, auth: Arc<AuthManager>) -> Self {
-        Self { client, auth }
+    pub fn new(http: Upstream, auth: Arc<AuthManager>) -> Self {
+        Self {
+            http,
+            auth,
+            limits: UploadLimits::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: UploadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Classify a file from its name and first bytes, rejecting types Qwen does not accept
+    pub fn detect(filename: &str, head: &[u8]) -> Result<FileKind> {
+        FileKind::detect(filename, head).ok_or_else(|| {
+            QwenError::InvalidRequest(format!("Unsupported file type: {}", filename))
+        })
     }
 
-    fn get_file_info(extension: &str) -> (&'static str, &'static str, &'static str, &'static str) {
-        match extension {
-            "jpg" | "jpeg" => ("image", "vision", "image", "image/jpeg"),
-            "png" => ("image", "vision", "image", "image/png"),
-            "gif" => ("image", "vision", "image", "image/gif"),
-            "webp" => ("image", "vision", "image", "image/webp"),
-            "bmp" => ("image", "vision", "image", "image/bmp"),
-
-            "mp4" => ("video", "video", "file", "video/mp4"),
-            "avi" => ("video", "video", "file", "video/x-msvideo"),
-            "mov" => ("video", "video", "file", "video/quicktime"),
-            "mkv" => ("video", "video", "file", "video/x-matroska"),
-
-            "mp3" => ("audio", "audio", "file", "audio/mpeg"),
-            "wav" => ("audio", "audio", "file", "audio/wav"),
-            "m4a" => ("audio", "audio", "file", "audio/mp4"),
-            "flac" => ("audio", "audio", "file", "audio/flac"),
-
-            "txt" => ("file", "document", "file", "text/plain"),
-            "md" => ("file", "document", "file", "text/markdown"),
-            "pdf" => ("file", "document", "file", "application/pdf"),
-            "doc" => ("file", "document", "file", "application/msword"),
-            "docx" => (
-                "file",
-                "document",
-                "file",
-                "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
-            ),
-            "csv" => ("file", "document", "file", "text/csv"),
-            "xls" => ("file", "document", "file", "application/vnd.ms-excel"),
-            "xlsx" => (
-                "file",
-                "document",
-                "file",
-                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
-            ),
-
-            _ => ("file", "document", "file", "application/octet-stream"),
+    /// Last component of a client-supplied name; directories are never part of the upload name
+    fn clean_filename(filename: &str) -> Result<String> {
+        filename
+            .rsplit(['/', '\\'])
+            .next()
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+            .map(str::to_string)
+            .ok_or_else(|| QwenError::InvalidRequest(format!("Invalid filename: {}", filename)))
+    }
+
+    /// Reject files above the limit for their class before anything is sent upstream
+    fn check_size(&self, filename: &str, kind: &FileKind, size: u64) -> Result<()> {
+        let max = self.limits.max_for(kind.file_class);
+        if size > max {
+            return Err(QwenError::InvalidRequest(format!(
+                "{} is {} bytes, the limit for {} files is {} bytes",
+                filename, size, kind.file_class, max
+            )));
         }
+        Ok(())
     }
 
     pub async fn upload_file(&self, file_path: &str, user_id: String) -> Result<QwenFile> {
@@ -73,20 +81,112 @@ impl FileUploader {
             .ok_or_else(|| QwenError::ApiError("Invalid filename".to_string()))?
             .to_string();
 
-        let file_data = tokio::fs::read(file_path).await?;
-        let filesize = file_data.len();
+        let file = tokio::fs::File::open(path).await?;
+        let size = file.metadata().await?.len();
 
-        let extension = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
+        self.upload_stream(&filename, file, size, user_id).await
+    }
 
-        let (filetype, file_class, show_type, content_type) = Self::get_file_info(&extension);
+    /// Upload data already in memory as `filename`
+    pub async fn upload_bytes(
+        &self,
+        filename: &str,
+        data: Bytes,
+        user_id: String,
+    ) -> Result<QwenFile> {
+        let filename = Self::clean_filename(filename)?;
+        let kind = Self::detect(&filename, &data[..data.len().min(SNIFF_LEN)])?;
+        let size = data.len() as u64;
+        self.check_size(&filename, &kind, size)?;
+
+        self.upload(&filename, kind, size, rquest::Body::from(data), user_id)
+            .await
+    }
+
+    /// Upload exactly `size` bytes read from `reader` as `filename`, streaming them to OSS.
+    /// The upload fails if the reader ends early.
+    pub async fn upload_stream<R>(
+        &self,
+        filename: &str,
+        mut reader: R,
+        size: u64,
+        user_id: String,
+    ) -> Result<QwenFile>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let filename = Self::clean_filename(filename)?;
+
+        // The start of the file decides its type; it is sent ahead of the rest of the stream
+        let mut head = vec![0u8; size.min(SNIFF_LEN as u64) as usize];
+        let mut filled = 0;
+        while filled < head.len() {
+            let read = reader.read(&mut head[filled..]).await?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        head.truncate(filled);
+
+        let kind = Self::detect(&filename, &head)?;
+        self.check_size(&filename, &kind, size)?;
+
+        let body = rquest::Body::wrap_stream(Self::read_chunks(head, reader, size));
+        self.upload(&filename, kind, size, body, user_id).await
+    }
+
+    /// `head` followed by chunks of `reader` until `size` bytes were sent
+    fn read_chunks<R>(
+        head: Vec<u8>,
+        mut reader: R,
+        size: u64,
+    ) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send + 'static
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        async_stream::try_stream! {
+            let mut remaining = size - head.len() as u64;
+            if !head.is_empty() {
+                yield head;
+            }
+            let mut buffer = vec![0u8; UPLOAD_CHUNK_SIZE];
+            while remaining > 0 {
+                let want = remaining.min(UPLOAD_CHUNK_SIZE as u64) as usize;
+                let read = reader.read(&mut buffer[..want]).await?;
+                if read == 0 {
+                    Err(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        format!("upload ended after {} of {} bytes", size - remaining, size),
+                    ))?;
+                }
+                remaining -= read as u64;
+                yield buffer[..read].to_vec();
+            }
+        }
+    }
+
+    async fn upload(
+        &self,
+        filename: &str,
+        kind: FileKind,
+        size: u64,
+        body: rquest::Body,
+        user_id: String,
+    ) -> Result<QwenFile> {
+        let filename = filename.to_string();
+        let filesize = size as usize;
+
+        let FileKind {
+            filetype,
+            file_class,
+            show_type,
+            content_type,
+        } = kind;
         let token = self.auth.get_token().await?;
 
-        let sts_url = format!("{}/api/v2/files/getstsToken", BASE_URL);
-        let headers = build_json_headers(Some(&token));
+        let sts_url = self.http.url("/api/v2/files/getstsToken");
+        let headers = self.http.json_headers(Some(&token));
 
         let sts_request = StsTokenRequest {
             filename: filename.clone(),
@@ -95,6 +195,7 @@ impl FileUploader {
         };
 
         let sts_response = self
+            .http
             .client
             .post(&sts_url)
             .headers(headers.clone())
@@ -102,7 +203,13 @@ impl FileUploader {
             .send()
             .await?;
 
+        let sts_status = sts_response.status();
         let sts_text = sts_response.text().await?;
+        let failed = || storage_error("STS token", sts_status, &sts_text);
+        if !sts_status.is_success() {
+            return Err(failed());
+        }
+
         let sts_data: StsTokenResponse = serde_json::from_str(&sts_text).map_err(|e| {
             QwenError::ApiError(format!(
                 "Failed to parse STS response: {} - {}",
@@ -111,13 +218,10 @@ impl FileUploader {
         })?;
 
         if !sts_data.success {
-            return Err(QwenError::ApiError(format!(
-                "Failed to get STS token: {}",
-                sts_text
-            )));
+            return Err(failed());
         }
 
-        self.upload_to_oss(&sts_data, &file_data, content_type)
+        self.upload_to_oss(&sts_data, body, size, content_type)
             .await?;
 
         let timestamp = std::time::SystemTime::now()
@@ -168,17 +272,29 @@ impl FileUploader {
     async fn upload_to_oss(
         &self,
         sts_data: &StsTokenResponse,
-        file_data: &[u8],
+        body: rquest::Body,
+        size: u64,
         content_type: &str,
     ) -> Result<()> {
         use hmac::{Hmac, Mac};
         use sha2::{Digest, Sha256};
         type HmacSha256 = Hmac<Sha256>;
 
-        let oss_url = format!(
-            "https://{}.{}/{}",
-            sts_data.data.bucketname, sts_data.data.endpoint, sts_data.data.file_path
-        );
+        let endpoint = &sts_data.data.endpoint;
+        // An endpoint with a scheme (e.g. a local mock) is addressed path-style
+        let oss_url = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            format!(
+                "{}/{}/{}",
+                endpoint.trim_end_matches('/'),
+                sts_data.data.bucketname,
+                sts_data.data.file_path
+            )
+        } else {
+            format!(
+                "https://{}.{}/{}",
+                sts_data.data.bucketname, endpoint, sts_data.data.file_path
+            )
+        };
 
         let oss_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
         let date_short = &oss_date[0..8];
@@ -255,6 +371,8 @@ impl FileUploader {
         let mut oss_headers = rquest::header::HeaderMap::new();
         oss_headers.insert("authorization", authorization.parse().unwrap());
         oss_headers.insert("content-type", content_type.parse().unwrap());
+        // Streamed bodies have no length of their own; OSS needs it up front
+        oss_headers.insert("content-length", size.into());
         oss_headers.insert("x-oss-date", oss_date.parse().unwrap());
         oss_headers.insert(
             "x-oss-security-token",
@@ -269,22 +387,53 @@ impl FileUploader {
         );
 
         let oss_response = self
+            .http
             .client
             .put(&oss_url)
             .headers(oss_headers)
-            .body(file_data.to_vec())
+            .body(body)
             .send()
             .await?;
 
         let status = oss_response.status();
         if !status.is_success() {
             let error_text = oss_response.text().await?;
-            return Err(QwenError::ApiError(format!(
-                "OSS upload failed ({}): {}",
-                status, error_text
-            )));
+            return Err(storage_error("OSS upload", status, &error_text));
         }
 
         Ok(())
     }
 }
+
+/// Error for a failed STS or OSS request.
+///
+/// These say nothing about the Qwen token: an OSS 403 usually means a bad signature,
+/// expired STS credentials or clock skew. They are never reported as `Unauthorized` or
+/// `RateLimited`, which would bench a healthy token.
+fn storage_error(action: &str, status: rquest::StatusCode, body: &str) -> QwenError {
+    let message = format!("{} failed ({}): {}", action, status, body);
+    if status.is_server_error() {
+        QwenError::UpstreamUnavailable(message)
+    } else {
+        QwenError::ApiError(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_filenames_lose_their_directories() {
+        assert_eq!(
+            FileUploader::clean_filename("../../etc/passwd").unwrap(),
+            "passwd"
+        );
+        assert_eq!(
+            FileUploader::clean_filename("C:\\Users\\me\\cat.png").unwrap(),
+            "cat.png"
+        );
+        assert!(FileUploader::clean_filename("uploads/..").is_err());
+        assert!(FileUploader::clean_filename("").is_err());
+    }
+}
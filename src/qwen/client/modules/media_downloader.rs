@@ -1,18 +1,35 @@
+use crate::events::{ClientEvent, EventSink};
 use crate::qwen::error::Result;
+use std::sync::Arc;
 
 pub struct MediaDownloader {
     client: rquest::Client,
+    events: Arc<dyn EventSink>,
 }
 
 impl MediaDownloader {
-    pub fn new(client: rquest::Client) -> Self {
-        Self { client }
+    pub fn new(client: rquest::Client, events: Arc<dyn EventSink>) -> Self {
+        Self { client, events }
     }
 
     /// Download media (image or video) from URL to local file
     pub async fn download_media(&self, url: &str, output_path: &str) -> Result<()> {
-        println!("⬇️  Downloading media to: {}", output_path);
+        self.events.emit(&ClientEvent::DownloadStarted {
+            path: output_path.to_string(),
+        });
 
+        let bytes = self.fetch_media(url).await?;
+        std::fs::write(output_path, bytes)?;
+
+        self.events.emit(&ClientEvent::DownloadFinished {
+            path: output_path.to_string(),
+            bytes: std::fs::metadata(output_path)?.len(),
+        });
+        Ok(())
+    }
+
+    /// Fetch media (image or video) from URL into memory
+    pub async fn fetch_media(&self, url: &str) -> Result<Vec<u8>> {
         let response = self.client.get(url).send().await?;
 
         if !response.status().is_success() {
@@ -22,13 +39,6 @@ impl MediaDownloader {
             )));
         }
 
-        let bytes = response.bytes().await?;
-        std::fs::write(output_path, bytes)?;
-
-        println!(
-            "✅ Downloaded successfully: {} bytes",
-            std::fs::metadata(output_path)?.len()
-        );
-        Ok(())
+        Ok(response.bytes().await?.to_vec())
     }
 }
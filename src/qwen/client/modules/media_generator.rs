@@ -1,16 +1,34 @@
 use super::chat_manager::ChatManager;
-use super::constants::{build_json_headers, BASE_URL};
-use super::streaming::ConversationBuilder;
+use super::constants::Upstream;
+use super::streaming::{ConversationBuilder, StreamingHandler};
+use crate::events::{ClientEvent, EventSink};
 use crate::qwen::error::Result;
-use crate::qwen::models::{ExtraData, QwenResponse, TaskResponse, TaskStatus};
+use crate::qwen::models::{
+    ChatCompletionRequest, ChatType, ExtraData, QwenFile, QwenResponse, TaskResponse, TaskStatus,
+    VideoPollConfig, VideoProgress, VideoRequest, VideoTask,
+};
+use crate::recording::{RecordedRequest, RecordedResponse};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub struct MediaGenerator {
-    client: rquest::Client,
+    http: Upstream,
+    events: Arc<dyn EventSink>,
+    poll: VideoPollConfig,
 }
 
 impl MediaGenerator {
-    pub fn new(client: rquest::Client) -> Self {
-        Self { client }
+    pub fn new(http: Upstream, events: Arc<dyn EventSink>) -> Self {
+        Self {
+            http,
+            events,
+            poll: VideoPollConfig::default(),
+        }
+    }
+
+    pub fn with_poll_config(mut self, poll: VideoPollConfig) -> Self {
+        self.poll = poll;
+        self
     }
 
     /// Generate an image from text prompt
@@ -30,9 +48,6 @@ impl MediaGenerator {
             chat_manager.create_or_get_chat(model).await?
         };
 
-        let url = format!("{}/api/v2/chat/completions?chat_id={}", BASE_URL, chat_id);
-        let headers = build_json_headers(Some(token));
-
         let parent_id = extra_data.and_then(|d| d.parent_id.clone());
         let image_size = size.map(|s| s.to_string());
 
@@ -40,13 +55,66 @@ impl MediaGenerator {
             prompt,
             model,
             vec![],
-            chat_id.clone(),
-            parent_id.clone(),
+            chat_id,
+            parent_id,
             "t2i",
             image_size,
         );
 
+        self.complete_image(completion_request, token, "Image generation")
+            .await
+    }
+
+    /// Edit an uploaded image following `prompt`; each edit starts from the source image alone
+    pub async fn edit_image(
+        &self,
+        prompt: &str,
+        source: QwenFile,
+        size: Option<&str>,
+        model_id: Option<&str>,
+        token: &str,
+        chat_manager: &ChatManager,
+    ) -> Result<QwenResponse> {
+        let model = model_id.unwrap_or("qwen3-max");
+        let chat_id = chat_manager.create_or_get_chat(model).await?;
+
+        let completion_request = ConversationBuilder::build_completion_request_with_chat_type(
+            prompt,
+            model,
+            vec![source],
+            chat_id,
+            None,
+            ChatType::ImageEdit.as_str(),
+            size.map(|s| s.to_string()),
+        );
+
+        self.complete_image(completion_request, token, "Image edit")
+            .await
+    }
+
+    /// Send an image request and collect the URL of the resulting image
+    async fn complete_image(
+        &self,
+        completion_request: ChatCompletionRequest,
+        token: &str,
+        action: &str,
+    ) -> Result<QwenResponse> {
+        let chat_id = completion_request.chat_id.clone();
+        let parent_id = completion_request.parent_id.clone();
+
+        let url = self
+            .http
+            .url(&format!("/api/v2/chat/completions?chat_id={}", chat_id));
+        let headers = self.http.json_headers(Some(token));
+        let recorded = RecordedRequest::new(
+            "POST",
+            &url,
+            &headers,
+            serde_json::to_value(&completion_request).ok(),
+        );
+
         let response = self
+            .http
             .client
             .post(&url)
             .headers(headers)
@@ -56,15 +124,26 @@ impl MediaGenerator {
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            return Err(crate::qwen::error::QwenError::ApiError(format!(
-                "Image generation failed ({}): {}",
-                status, error_text
-            )));
+            return Err(crate::qwen::error::QwenError::from_response(
+                action,
+                status,
+                &headers,
+                &error_text,
+            ));
         }
 
-        let output =
-            super::streaming::StreamingHandler::handle_streaming_response(response).await?;
+        let head = RecordedResponse::head(status.as_u16(), response.headers());
+        let body = self
+            .http
+            .recorder
+            .tap("qwen-image", recorded, head, response.bytes_stream());
+        let output = StreamingHandler::handle_streaming_response(
+            StreamingHandler::decode(body),
+            self.events.as_ref(),
+        )
+        .await?;
 
         Ok(QwenResponse {
             content: output.content,
@@ -73,6 +152,7 @@ impl MediaGenerator {
             parent_id,
             web_search_results: None,
             thinking_content: None,
+            images: output.images,
         })
     }
 
@@ -93,47 +173,42 @@ impl MediaGenerator {
             extra_data,
             token,
             chat_manager,
-            |_, _| {},
+            |_| {},
         )
         .await
     }
 
-    /// Generate a video with progress callback
-    pub async fn generate_video_with_progress<F>(
+    /// Submit a video generation task and return as soon as upstream accepts it.
+    /// With a `first_frame` the video starts from that image.
+    pub async fn start_video_task(
         &self,
-        prompt: &str,
-        size: Option<&str>,
-        model_id: Option<&str>,
-        extra_data: Option<&ExtraData>,
+        request: &VideoRequest,
         token: &str,
         chat_manager: &ChatManager,
-        progress_callback: F,
-    ) -> Result<QwenResponse>
-    where
-        F: Fn(&str, u8) + Send + Sync,
-    {
-        let model = model_id.unwrap_or("qwen3-max");
+    ) -> Result<VideoTask> {
+        let model = request.model.as_deref().unwrap_or("qwen3-max");
+        let extra_data = request.extra_data.as_ref();
         let chat_id = if let Some(data) = extra_data {
             data.chat_id.clone()
         } else {
             chat_manager.create_or_get_chat(model).await?
         };
 
-        let url = format!("{}/api/v2/chat/completions?chat_id={}", BASE_URL, chat_id);
-        let headers = build_json_headers(Some(token));
+        let url = self
+            .http
+            .url(&format!("/api/v2/chat/completions?chat_id={}", chat_id));
+        let headers = self.http.json_headers(Some(token));
 
         let parent_id = extra_data.and_then(|d| d.parent_id.clone());
-        let video_size = size
-            .map(|s| s.to_string())
-            .or_else(|| Some("16:9".to_string()));
+        let video_size = request.size.clone().or_else(|| Some("16:9".to_string()));
 
         // Build request with stream=false for video generation
         let mut completion_request = ConversationBuilder::build_completion_request_with_chat_type(
-            prompt,
+            &request.prompt,
             model,
-            vec![],
+            request.first_frame.iter().cloned().collect(),
             chat_id.clone(),
-            parent_id.clone(),
+            parent_id,
             "t2v",
             video_size,
         );
@@ -142,20 +217,24 @@ impl MediaGenerator {
         completion_request.stream = false;
 
         let response = self
+            .http
             .client
             .post(&url)
-            .headers(headers.clone())
+            .headers(headers)
             .json(&completion_request)
             .send()
             .await?;
 
         let status = response.status();
         if !status.is_success() {
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            return Err(crate::qwen::error::QwenError::ApiError(format!(
-                "Video generation failed ({}): {}",
-                status, error_text
-            )));
+            return Err(crate::qwen::error::QwenError::from_response(
+                "Video generation",
+                status,
+                &headers,
+                &error_text,
+            ));
         }
 
         // Parse task response
@@ -173,83 +252,210 @@ impl MediaGenerator {
                 crate::qwen::error::QwenError::ApiError("No task_id in response".to_string())
             })?;
 
-        println!("🎬 Video generation started, task_id: {}", task_id);
-        progress_callback("started", 0);
+        self.events.emit(&ClientEvent::VideoTaskStarted {
+            task_id: task_id.clone(),
+        });
+
+        Ok(VideoTask {
+            task_id,
+            chat_id,
+            response_id: task_response.data.message_id,
+            parent_id: Some(task_response.data.parent_id),
+        })
+    }
+
+    /// Ask upstream once for the state of a video task
+    pub async fn task_status(&self, task_id: &str, token: &str) -> Result<TaskStatus> {
+        let url = self.http.url(&format!("/api/v1/tasks/status/{}", task_id));
+        let headers = self.http.json_headers(Some(token));
+
+        let response = self.http.client.get(&url).headers(headers).send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let error_text = response.text().await?;
+            return Err(crate::qwen::error::QwenError::from_response(
+                "Task status",
+                status,
+                &headers,
+                &error_text,
+            ));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Generate a video with progress callback
+    pub async fn generate_video_with_progress<F>(
+        &self,
+        prompt: &str,
+        size: Option<&str>,
+        model_id: Option<&str>,
+        extra_data: Option<&ExtraData>,
+        token: &str,
+        chat_manager: &ChatManager,
+        progress_callback: F,
+    ) -> Result<QwenResponse>
+    where
+        F: Fn(&VideoProgress) + Send + Sync,
+    {
+        let request = VideoRequest {
+            prompt: prompt.to_string(),
+            size: size.map(|s| s.to_string()),
+            model: model_id.map(|m| m.to_string()),
+            first_frame: None,
+            extra_data: extra_data.cloned(),
+        };
+        let task = self.start_video_task(&request, token, chat_manager).await?;
 
         // Poll task status
         let video_url = self
-            .poll_task_status(&task_id, token, &progress_callback)
+            .wait_for_video(&task.task_id, token, progress_callback)
             .await?;
 
         Ok(QwenResponse {
             content: video_url,
-            response_id: task_response.data.message_id,
-            chat_id: Some(chat_id),
-            parent_id: Some(task_response.data.parent_id),
+            response_id: task.response_id,
+            chat_id: Some(task.chat_id),
+            parent_id: task.parent_id,
             web_search_results: None,
             thinking_content: None,
+            images: vec![],
         })
     }
 
-    /// Poll task status until completion
-    async fn poll_task_status<F>(
+    /// Poll a video task until it succeeds, fails or the configured timeout passes.
+    ///
+    /// Failed status requests are retried with exponential backoff, capped at
+    /// `max_backoff`; the delay goes back to `interval` after the next good poll.
+    pub async fn wait_for_video<F>(
         &self,
         task_id: &str,
         token: &str,
-        progress_callback: &F,
+        progress_callback: F,
     ) -> Result<String>
     where
-        F: Fn(&str, u8) + Send + Sync,
+        F: Fn(&VideoProgress) + Send + Sync,
     {
-        let url = format!("{}/api/v1/tasks/status/{}", BASE_URL, task_id);
-        let headers = build_json_headers(Some(token));
+        let started = Instant::now();
+        let mut delay = self.poll.interval;
 
-        let max_attempts = 300; // 5 minutes with 1 second interval
-        let poll_interval = std::time::Duration::from_secs(1);
-
-        for attempt in 0..max_attempts {
-            tokio::time::sleep(poll_interval).await;
+        loop {
+            if started.elapsed() >= self.poll.timeout {
+                return Err(crate::qwen::error::QwenError::ApiError(format!(
+                    "Video generation timed out after {}s",
+                    self.poll.timeout.as_secs()
+                )));
+            }
 
-            let response = self
-                .client
-                .get(&url)
-                .headers(headers.clone())
-                .send()
-                .await?;
+            tokio::time::sleep(delay).await;
 
-            if !response.status().is_success() {
-                continue;
-            }
+            let task_status = match self.task_status(task_id, token).await {
+                Ok(task_status) => task_status,
+                Err(
+                    crate::qwen::error::QwenError::ApiError(_)
+                    | crate::qwen::error::QwenError::UpstreamUnavailable(_)
+                    | crate::qwen::error::QwenError::RateLimited { .. },
+                ) => {
+                    delay = (delay * 2).min(self.poll.max_backoff);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            delay = self.poll.interval;
 
-            let task_status: TaskStatus = response.json().await?;
+            let progress = VideoProgress {
+                status: task_status.task_status.clone(),
+                message: task_status.message.clone(),
+                remaining: parse_remaining_time(&task_status.remaining_time),
+                elapsed: started.elapsed(),
+            };
+            progress_callback(&progress);
+            self.events.emit(&ClientEvent::VideoProgress(progress));
 
             match task_status.task_status.as_str() {
                 "success" => {
-                    progress_callback("success", 100);
-                    println!("\n✅ Video generation completed!");
+                    self.events.emit(&ClientEvent::VideoCompleted);
                     return Ok(task_status.content);
                 }
                 "failed" => {
-                    progress_callback("failed", 0);
                     return Err(crate::qwen::error::QwenError::ApiError(format!(
                         "Video generation failed: {}",
                         task_status.message
                     )));
                 }
-                "running" => {
-                    let progress = ((attempt as f32 / max_attempts as f32) * 100.0) as u8;
-                    progress_callback("running", progress);
-                    if attempt % 5 == 0 {
-                        print!(".");
-                        std::io::Write::flush(&mut std::io::stdout()).ok();
-                    }
-                }
                 _ => {}
             }
         }
+    }
+}
+
+/// Read upstream's `remaining_time`, e.g. `"90"`, `"1m30s"`, `"2 min"`, `"约1.5分钟"`, `"1小时"` or
+/// `"01:30"`
+fn parse_remaining_time(text: &str) -> Option<Duration> {
+    let text = text.trim();
+
+    if text.contains(':') {
+        let mut secs = 0u64;
+        for part in text.split(':') {
+            secs = secs * 60 + part.trim().parse::<u64>().ok()?;
+        }
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut total = 0.0;
+    let mut found = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        // Prefixes like "约" and the rest of a unit ("min", "分钟") carry no number
+        if !c.is_ascii_digit() {
+            chars.next();
+            continue;
+        }
+
+        let mut number = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+            number.push(c);
+        }
+        let value: f64 = number.parse().ok()?;
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let unit = match chars.next() {
+            Some('h' | 'H' | '小' | '时') => 3600.0,
+            Some('m' | 'M' | '分') => 60.0,
+            // A trailing bare number counts as seconds
+            Some('s' | 'S' | '秒') | None => 1.0,
+            // Not a duration we understand; better no estimate than a wrong one
+            Some(_) => return None,
+        };
+        total += value * unit;
+        found = true;
+    }
+
+    found.then(|| Duration::from_secs(total.round() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining_time_formats() {
+        let secs = |text| parse_remaining_time(text).map(|d| d.as_secs());
 
-        Err(crate::qwen::error::QwenError::ApiError(
-            "Video generation timeout".to_string(),
-        ))
+        assert_eq!(secs("90"), Some(90));
+        assert_eq!(secs("1m30s"), Some(90));
+        assert_eq!(secs("约2分钟"), Some(120));
+        assert_eq!(secs("1分30秒"), Some(90));
+        assert_eq!(secs("1小时"), Some(3600));
+        assert_eq!(secs("01:30"), Some(90));
+        assert_eq!(secs("2 min"), Some(120));
+        assert_eq!(secs("约 2 分钟"), Some(120));
+        assert_eq!(secs("1.5分钟"), Some(90));
+        assert_eq!(secs("3x"), None);
+        assert_eq!(secs(""), None);
+        assert_eq!(secs("即将完成"), None);
     }
 }
@@ -1,6 +1,7 @@
 pub mod auth;
 pub mod chat_manager;
 pub mod constants;
+pub mod file_type;
 pub mod file_uploader;
 pub mod media_downloader;
 pub mod media_generator;
@@ -1,3 +1,4 @@
+use crate::qwen::error::{QwenError, Result};
 use crate::qwen::models::Model;
 
 pub struct ModelSelector;
@@ -38,6 +39,51 @@ impl ModelSelector {
             })
     }
 
+    /// Check that `model_id` supports thinking and that `budget` fits within its
+    /// `max_thinking_generation_length`. Returns the budget to send upstream.
+    pub fn validate_thinking(
+        models: &[Model],
+        model_id: &str,
+        budget: Option<u32>,
+    ) -> Result<Option<u32>> {
+        let model = models
+            .iter()
+            .find(|m| m.id == model_id)
+            .ok_or_else(|| QwenError::ModelNotFound(format!("Unknown model: {}", model_id)))?;
+
+        // Without an info block there is nothing saying the model can think
+        let supports_thinking = model
+            .info
+            .as_ref()
+            .is_some_and(|info| info.meta.capabilities.thinking);
+        if !supports_thinking {
+            return Err(QwenError::InvalidRequest(format!(
+                "Model {} does not support thinking",
+                model_id
+            )));
+        }
+
+        let Some(budget) = budget else {
+            return Ok(None);
+        };
+
+        let max = Self::get_model_thinking_budget(models, model_id).ok_or_else(|| {
+            QwenError::InvalidRequest(format!(
+                "Model {} does not accept a thinking budget",
+                model_id
+            ))
+        })?;
+
+        if budget == 0 || budget > max {
+            return Err(QwenError::InvalidRequest(format!(
+                "Thinking budget for {} must be between 1 and {}, got {}",
+                model_id, max, budget
+            )));
+        }
+
+        Ok(Some(budget))
+    }
+
     /// Get all models that support thinking
     pub fn get_thinking_capable_models(models: Vec<Model>) -> Vec<Model> {
         models
@@ -172,3 +218,53 @@ impl ModelSelector {
             .unwrap_or_else(|| "qwen3-max".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn models() -> Vec<Model> {
+        serde_json::from_value(serde_json::json!([
+            {
+                "id": "qwen3-max", "name": "Qwen3-Max", "object": "model", "owned_by": "qwen",
+                "info": { "id": "qwen3-max", "name": "Qwen3-Max", "meta": {
+                    "capabilities": { "thinking": false }
+                }}
+            },
+            {
+                "id": "qwen3-235b-a22b", "name": "Qwen3-235B", "object": "model", "owned_by": "qwen",
+                "info": { "id": "qwen3-235b-a22b", "name": "Qwen3-235B", "meta": {
+                    "capabilities": { "thinking": true, "thinking_budget": true },
+                    "max_thinking_generation_length": 38912
+                }}
+            },
+            { "id": "qwen-legacy", "name": "Qwen Legacy", "object": "model", "owned_by": "qwen" }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn thinking_is_checked_against_model_capabilities() {
+        let models = models();
+
+        assert!(matches!(
+            ModelSelector::validate_thinking(&models, "qwen3-max", None),
+            Err(QwenError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            ModelSelector::validate_thinking(&models, "qwen-legacy", None),
+            Err(QwenError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            ModelSelector::validate_thinking(&models, "qwen-unknown", None),
+            Err(QwenError::ModelNotFound(_))
+        ));
+        assert_eq!(
+            ModelSelector::validate_thinking(&models, "qwen3-235b-a22b", Some(1024)).unwrap(),
+            Some(1024)
+        );
+        assert!(
+            ModelSelector::validate_thinking(&models, "qwen3-235b-a22b", Some(50_000)).is_err()
+        );
+    }
+}
@@ -1,125 +1,224 @@
-use crate::qwen::error::Result;
+use crate::events::{ClientEvent, EventSink};
+use crate::qwen::error::{QwenError, Result};
 use crate::qwen::models::{
-    ChatCompletionRequest, Extra, FeatureConfig, Meta, QwenFile, QwenMessage,
+    ChatCompletionRequest, Extra, FeatureConfig, Meta, QwenEvent, QwenFile, QwenMessage,
+    WebSearchInfo,
 };
-use futures_util::stream::StreamExt;
+use crate::sse;
+use futures_util::stream::{Stream, StreamExt};
+use std::pin::Pin;
 use uuid::Uuid;
 
+/// Boxed stream of events for a single completion
+pub type QwenEventStream = Pin<Box<dyn Stream<Item = Result<QwenEvent>> + Send>>;
+
 pub struct StreamingHandler;
 
 impl StreamingHandler {
-    pub async fn handle_streaming_response(response: rquest::Response) -> Result<StreamingOutput> {
-        let mut stream = response.bytes_stream();
-        let mut content = String::new();
-        let mut response_id: Option<String> = None;
-        let mut thinking_content = String::new();
-        let mut web_search_results: Option<Vec<crate::qwen::models::WebSearchInfo>> = None;
-        let mut current_phase = String::new();
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            let text = String::from_utf8_lossy(&chunk);
-
-            for line in text.lines() {
-                if line.starts_with("data: ") {
-                    let data = line.strip_prefix("data: ").unwrap().trim();
-                    if data.is_empty() || data == "[DONE]" {
-                        continue;
+    /// Collect a completion, reporting phases, thinking and answer deltas to `events`
+    pub async fn handle_streaming_response(
+        stream: QwenEventStream,
+        events: &dyn EventSink,
+    ) -> Result<StreamingOutput> {
+        let output = Self::collect(stream, |event| match event {
+            QwenEvent::ThinkingDelta(text) => {
+                events.emit(&ClientEvent::ThinkingDelta(text.clone()))
+            }
+            QwenEvent::AnswerDelta(text) => events.emit(&ClientEvent::AnswerDelta(text.clone())),
+            QwenEvent::PhaseChanged(phase) => events.emit(&ClientEvent::PhaseChanged {
+                phase: phase.clone(),
+            }),
+            _ => {}
+        })
+        .await?;
+
+        events.emit(&ClientEvent::AnswerFinished);
+
+        Ok(output)
+    }
+
+    /// Turn the SSE body of a completion into typed events.
+    /// The stream always ends with `Done` unless the body itself fails.
+    pub fn event_stream(response: rquest::Response) -> QwenEventStream {
+        Self::decode(response.bytes_stream())
+    }
+
+    /// Like [`event_stream`](Self::event_stream), for a body from any byte stream,
+    /// such as a recorded exchange being replayed
+    pub fn decode<S, B, E>(body: S) -> QwenEventStream
+    where
+        S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+        B: AsRef<[u8]> + Send,
+        E: Send,
+        QwenError: From<E>,
+    {
+        Box::pin(async_stream::try_stream! {
+            let mut stream = Box::pin(sse::events(body));
+            let mut current_phase = String::new();
+            let mut response_created = false;
+
+            while let Some(event) = stream.next().await {
+                let event = event?;
+                for event in Self::parse_data(&event.data, &mut current_phase, &mut response_created) {
+                    let is_error = matches!(event, QwenEvent::Error { .. });
+                    yield event;
+                    if is_error {
+                        return;
                     }
+                }
+            }
 
-                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
-                        if let Some(error) = json["error"].as_object() {
-                            let code = error["code"].as_str().unwrap_or("unknown");
-                            let details = error["details"].as_str().unwrap_or("no details");
-                            return Err(crate::qwen::error::QwenError::ApiError(format!(
-                                "Server error: {} - {}",
-                                code, details
-                            )));
-                        }
+            yield QwenEvent::Done;
+        })
+    }
 
-                        if response_id.is_none() {
-                            if let Some(created) = json["response.created"].as_object() {
-                                if let Some(rid) = created["response_id"].as_str() {
-                                    response_id = Some(rid.to_string());
-                                }
-                            }
-                        }
+    /// Events carried by the `data` of one SSE event
+    fn parse_data(
+        data: &str,
+        current_phase: &mut String,
+        response_created: &mut bool,
+    ) -> Vec<QwenEvent> {
+        let mut events = Vec::new();
+
+        let data = data.trim();
+        if data.is_empty() || data == "[DONE]" {
+            return events;
+        }
+
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(data) else {
+            return events;
+        };
+
+        if let Some(error) = json["error"].as_object() {
+            events.push(QwenEvent::Error {
+                code: error["code"].as_str().unwrap_or("unknown").to_string(),
+                details: error["details"]
+                    .as_str()
+                    .unwrap_or("no details")
+                    .to_string(),
+            });
+            return events;
+        }
+
+        if !*response_created {
+            if let Some(created) = json["response.created"].as_object() {
+                if let Some(rid) = created["response_id"].as_str() {
+                    *response_created = true;
+                    events.push(QwenEvent::ResponseCreated {
+                        response_id: rid.to_string(),
+                        chat_id: created
+                            .get("chat_id")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                    });
+                }
+            }
+        }
+
+        let Some(choices) = json["choices"].as_array() else {
+            return events;
+        };
 
-                        if let Some(choices) = json["choices"].as_array() {
-                            for choice in choices {
-                                if let Some(delta) = choice["delta"].as_object() {
-                                    if let Some(phase) = delta.get("phase").and_then(|v| v.as_str())
-                                    {
-                                        current_phase = phase.to_string();
-                                    }
-
-                                    if current_phase == "thinking" {
-                                        if let Some(think_content) =
-                                            delta.get("content").and_then(|v| v.as_str())
-                                        {
-                                            thinking_content.push_str(think_content);
-                                            print!("{}", think_content);
-                                            std::io::Write::flush(&mut std::io::stdout()).ok();
-                                        }
-                                    }
-
-                                    if current_phase == "web_search" {
-                                        if let Some(extra) = delta.get("extra") {
-                                            if let Some(search_info) = extra.get("web_search_info")
-                                            {
-                                                if let Ok(results) = serde_json::from_value::<
-                                                    Vec<crate::qwen::models::WebSearchInfo>,
-                                                >(
-                                                    search_info.clone()
-                                                ) {
-                                                    web_search_results = Some(results);
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    if current_phase == "image_gen" {
-                                        if let Some(text) =
-                                            delta.get("content").and_then(|v| v.as_str())
-                                        {
-                                            if !text.is_empty() {
-                                                content = text.to_string(); // Replace instead of append for image URLs
-                                            }
-                                        }
-                                    }
-
-                                    if current_phase == "answer"
-                                        || (!current_phase.contains("thinking")
-                                            && !current_phase.contains("search")
-                                            && current_phase != "image_gen")
-                                    {
-                                        if let Some(text) =
-                                            delta.get("content").and_then(|v| v.as_str())
-                                        {
-                                            content.push_str(text);
-                                            print!("{}", text);
-                                            std::io::Write::flush(&mut std::io::stdout()).ok();
-                                        }
-                                    }
-                                }
-                            }
+        for choice in choices {
+            let Some(delta) = choice["delta"].as_object() else {
+                continue;
+            };
+
+            if let Some(phase) = delta.get("phase").and_then(|v| v.as_str()) {
+                if phase != current_phase {
+                    *current_phase = phase.to_string();
+                    events.push(QwenEvent::PhaseChanged(phase.to_string()));
+                }
+            }
+
+            let text = delta.get("content").and_then(|v| v.as_str());
+
+            match current_phase.as_str() {
+                "thinking" => {
+                    if let Some(text) = text {
+                        events.push(QwenEvent::ThinkingDelta(text.to_string()));
+                    }
+                }
+                "web_search" => {
+                    if let Some(search_info) =
+                        delta.get("extra").and_then(|e| e.get("web_search_info"))
+                    {
+                        if let Ok(results) =
+                            serde_json::from_value::<Vec<WebSearchInfo>>(search_info.clone())
+                        {
+                            events.push(QwenEvent::WebSearchResults(results));
                         }
                     }
                 }
+                "image_gen" => {
+                    if let Some(text) = text.filter(|t| !t.is_empty()) {
+                        events.push(QwenEvent::ImageGenerated(text.to_string()));
+                    }
+                }
+                // Recaps text already sent during `thinking`
+                "thinking_summary" => {}
+                // `answer`, and the steps of deep research such as `ResearchPlanning`
+                _ => {
+                    if let Some(text) = text {
+                        events.push(QwenEvent::AnswerDelta(text.to_string()));
+                    }
+                }
             }
         }
 
-        println!();
+        events
+    }
+
+    /// Drain an event stream into a `StreamingOutput`, calling `on_event` for every event.
+    /// An upstream `Error` event is turned into the `QwenError` its code describes.
+    pub async fn collect<S, F>(mut stream: S, mut on_event: F) -> Result<StreamingOutput>
+    where
+        S: Stream<Item = Result<QwenEvent>> + Unpin,
+        F: FnMut(&QwenEvent),
+    {
+        let mut content = String::new();
+        let mut response_id = String::new();
+        let mut thinking_content = String::new();
+        let mut web_search_results: Option<Vec<WebSearchInfo>> = None;
+        let mut images: Vec<String> = Vec::new();
+
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            on_event(&event);
+
+            match event {
+                QwenEvent::ResponseCreated {
+                    response_id: rid, ..
+                } => response_id = rid,
+                QwenEvent::ThinkingDelta(text) => thinking_content.push_str(&text),
+                QwenEvent::AnswerDelta(text) => content.push_str(&text),
+                QwenEvent::WebSearchResults(results) => web_search_results = Some(results),
+                // Replace instead of append for image URLs; the same URL may be repeated
+                QwenEvent::ImageGenerated(url) => {
+                    if !images.contains(&url) {
+                        images.push(url.clone());
+                    }
+                    content = url;
+                }
+                QwenEvent::Error { code, details } => {
+                    return Err(QwenError::from_event(&code, &details));
+                }
+                QwenEvent::PhaseChanged(_) => {}
+                QwenEvent::Done => break,
+            }
+        }
 
         Ok(StreamingOutput {
             content,
-            response_id: response_id.unwrap_or_default(),
+            response_id,
             thinking_content: if thinking_content.is_empty() {
                 None
             } else {
                 Some(thinking_content)
             },
             web_search_results,
+            images,
         })
     }
 }
@@ -128,7 +227,8 @@ pub struct StreamingOutput {
     pub content: String,
     pub response_id: String,
     pub thinking_content: Option<String>,
-    pub web_search_results: Option<Vec<crate::qwen::models::WebSearchInfo>>,
+    pub web_search_results: Option<Vec<WebSearchInfo>>,
+    pub images: Vec<String>,
 }
 
 pub struct ConversationBuilder;
@@ -140,6 +240,7 @@ impl ConversationBuilder {
         files: Vec<QwenFile>,
         parent_id: Option<String>,
         chat_type: &str,
+        feature_config: FeatureConfig,
     ) -> QwenMessage {
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -157,12 +258,7 @@ impl ConversationBuilder {
             timestamp,
             models: vec![model.to_string()],
             chat_type: chat_type.to_string(),
-            feature_config: FeatureConfig {
-                thinking_enabled: false,
-                output_schema: "phase".to_string(),
-                research_mode: "normal".to_string(),
-                thinking_budget: None,
-            },
+            feature_config,
             extra: Extra {
                 meta: Meta {
                     sub_chat_type: chat_type.to_string(),
@@ -172,23 +268,21 @@ impl ConversationBuilder {
         }
     }
 
-    pub fn build_completion_request(
+    /// A completion request for any chat type
+    pub fn build_request(
         message: &str,
         model: &str,
         files: Vec<QwenFile>,
         chat_id: String,
         parent_id: Option<String>,
-        enable_search: bool,
-        _enable_thinking: bool,
-        _thinking_budget: Option<u32>,
+        chat_type: &str,
+        feature_config: FeatureConfig,
     ) -> ChatCompletionRequest {
         let timestamp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
-        let chat_type = if enable_search { "search" } else { "t2t" };
-
         ChatCompletionRequest {
             stream: true,
             incremental_output: true,
@@ -197,13 +291,41 @@ impl ConversationBuilder {
             model: model.to_string(),
             parent_id: parent_id.clone(),
             messages: vec![Self::build_message(
-                message, model, files, parent_id, chat_type,
+                message,
+                model,
+                files,
+                parent_id,
+                chat_type,
+                feature_config,
             )],
             timestamp,
             size: None,
         }
     }
 
+    pub fn build_completion_request(
+        message: &str,
+        model: &str,
+        files: Vec<QwenFile>,
+        chat_id: String,
+        parent_id: Option<String>,
+        enable_search: bool,
+        enable_thinking: bool,
+        thinking_budget: Option<u32>,
+    ) -> ChatCompletionRequest {
+        let chat_type = if enable_search { "search" } else { "t2t" };
+
+        Self::build_request(
+            message,
+            model,
+            files,
+            chat_id,
+            parent_id,
+            chat_type,
+            FeatureConfig::new(enable_thinking, thinking_budget),
+        )
+    }
+
     pub fn build_completion_request_with_chat_type(
         message: &str,
         model: &str,
@@ -213,23 +335,139 @@ impl ConversationBuilder {
         chat_type: &str,
         size: Option<String>,
     ) -> ChatCompletionRequest {
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         ChatCompletionRequest {
-            stream: true,
-            incremental_output: true,
-            chat_id,
-            chat_mode: "normal".to_string(),
-            model: model.to_string(),
-            parent_id: parent_id.clone(),
-            messages: vec![Self::build_message(
-                message, model, files, parent_id, chat_type,
-            )],
-            timestamp,
             size,
+            ..Self::build_request(
+                message,
+                model,
+                files,
+                chat_id,
+                parent_id,
+                chat_type,
+                FeatureConfig::default(),
+            )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::fixture;
+
+    #[test]
+    fn phase_change_is_reported_once_per_phase() {
+        let mut phase = String::new();
+        let mut created = true;
+        let payloads = [
+            r#"{"choices":[{"delta":{"phase":"ResearchPlanning","content":"plan"}}]}"#,
+            r#"{"choices":[{"delta":{"phase":"ResearchPlanning","content":" more"}}]}"#,
+            r#"{"choices":[{"delta":{"phase":"answer","content":"done"}}]}"#,
+        ];
+
+        let phases: Vec<String> = payloads
+            .iter()
+            .flat_map(|data| StreamingHandler::parse_data(data, &mut phase, &mut created))
+            .filter_map(|event| match event {
+                QwenEvent::PhaseChanged(phase) => Some(phase),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(phases, ["ResearchPlanning", "answer"]);
+    }
+
+    #[test]
+    fn research_phases_carry_content() {
+        let mut phase = String::new();
+        let mut created = true;
+        let payloads = [
+            r#"{"choices":[{"delta":{"phase":"thinking","content":"hmm"}}]}"#,
+            r#"{"choices":[{"delta":{"phase":"ResearchPlanning","content":"Plan: "}}]}"#,
+            r#"{"choices":[{"delta":{"phase":"ResearchSearching","content":"read 3 sources. "}}]}"#,
+            r#"{"choices":[{"delta":{"phase":"answer","content":"Report"}}]}"#,
+        ];
+
+        let answer: String = payloads
+            .iter()
+            .flat_map(|data| StreamingHandler::parse_data(data, &mut phase, &mut created))
+            .filter_map(|event| match event {
+                QwenEvent::AnswerDelta(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(answer, "Plan: read 3 sources. Report");
+    }
+
+    #[tokio::test]
+    async fn collect_keeps_every_generated_image() {
+        let events = [
+            QwenEvent::ImageGenerated("https://cdn/a.png".into()),
+            QwenEvent::ImageGenerated("https://cdn/a.png".into()),
+            QwenEvent::ImageGenerated("https://cdn/b.png".into()),
+            QwenEvent::Done,
+        ];
+        let stream = futures_util::stream::iter(events.into_iter().map(Ok));
+
+        let output = StreamingHandler::collect(stream, |_| {}).await.unwrap();
+
+        assert_eq!(output.images, ["https://cdn/a.png", "https://cdn/b.png"]);
+        assert_eq!(output.content, "https://cdn/b.png");
+    }
+
+    #[tokio::test]
+    async fn replays_a_search_with_thinking() {
+        let exchange = fixture("qwen/search_thinking.json");
+
+        // Splitting inside multi-byte characters must not change the result
+        for chunk_size in [0, 1, 7, 64] {
+            let stream = StreamingHandler::decode(exchange.replay(chunk_size));
+            let output = StreamingHandler::collect(stream, |_| {}).await.unwrap();
+
+            assert_eq!(output.response_id, "3a9d7c21-0b4e-4f68-a1c5-9e2d8b7f6a40");
+            assert_eq!(
+                output.thinking_content.as_deref(),
+                Some("用户想了解 Rust 的所有权。")
+            );
+            assert_eq!(output.content, "Rust 通过所有权管理内存，无需垃圾回收。");
+            assert_eq!(
+                output.web_search_results.unwrap()[0].title,
+                "Rust Programming Language"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn replayed_error_event_ends_the_stream() {
+        let exchange = fixture("qwen/upstream_error.json");
+
+        let events: Vec<QwenEvent> = StreamingHandler::decode(exchange.replay(16))
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        assert!(
+            matches!(events.last(), Some(QwenEvent::Error { code, .. }) if code == "RateLimited")
+        );
+        let error = StreamingHandler::collect(StreamingHandler::decode(exchange.replay(0)), |_| {})
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("RateLimited"), "{}", error);
+        assert!(matches!(error, QwenError::RateLimited { .. }), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn replays_an_image_generation() {
+        let exchange = fixture("qwen/image_generation.json");
+
+        let stream = StreamingHandler::decode(exchange.replay(32));
+        let output = StreamingHandler::collect(stream, |_| {}).await.unwrap();
+
+        assert_eq!(
+            output.images,
+            ["https://cdn.qwenlm.ai/output/3f8a/t2i/2025-10-18/7c1d9e2a.png?key=signed"]
+        );
+    }
+}
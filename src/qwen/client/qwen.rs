@@ -1,54 +1,102 @@
-use crate::qwen::error::Result;
-use crate::qwen::models::{ExtraData, Model, QwenFile, QwenResponse};
+use crate::events::EventSink;
+use crate::qwen::error::{QwenError, Result};
+use crate::qwen::models::{
+    ChatRequest, ChatType, ExtraData, FeatureConfig, Model, QwenEvent, QwenFile, QwenResponse,
+    TaskStatus, UploadLimits, VideoPollConfig, VideoProgress, VideoRequest, VideoTask,
+};
+use crate::recording::{RecordedRequest, RecordedResponse};
 
+use super::builder::QwenClientBuilder;
 use super::modules::{
     auth::AuthManager,
     chat_manager::ChatManager,
-    constants::build_json_headers,
+    constants::Upstream,
+    file_type::FileKind,
     file_uploader::FileUploader,
     media_downloader::MediaDownloader,
     media_generator::MediaGenerator,
     model_selector::ModelSelector,
-    streaming::{ConversationBuilder, StreamingHandler},
+    streaming::{ConversationBuilder, QwenEventStream, StreamingHandler},
 };
+use super::request::QwenRequestBuilder;
+use futures_util::StreamExt;
 use std::sync::Arc;
+use tokio::io::AsyncRead;
 
 pub struct QwenClient {
-    client: rquest::Client,
+    http: Upstream,
     auth: Arc<AuthManager>,
     chat_manager: ChatManager,
     file_uploader: FileUploader,
     media_generator: MediaGenerator,
     media_downloader: MediaDownloader,
+    events: Arc<dyn EventSink>,
+    video_poll: VideoPollConfig,
 }
 
 impl QwenClient {
+    /// Base URL, proxy, timeouts, extra headers or a shared HTTP client
+    pub fn builder() -> QwenClientBuilder {
+        QwenClientBuilder::new()
+    }
+
     pub fn new(email: String, password: String) -> Result<Self> {
-        let client = rquest::Client::builder().cookie_store(true).build()?;
-        let auth = Arc::new(AuthManager::new(email, password, client.clone()));
-
-        Ok(Self {
-            chat_manager: ChatManager::new(client.clone(), auth.clone()),
-            file_uploader: FileUploader::new(client.clone(), auth.clone()),
-            media_generator: MediaGenerator::new(client.clone()),
-            media_downloader: MediaDownloader::new(client.clone()),
-            auth,
-            client: rquest::Client::builder().cookie_store(true).build()?,
-        })
+        Self::builder().credentials(email, password).build()
     }
 
     pub fn with_token(token: String) -> Result<Self> {
-        let client = rquest::Client::builder().cookie_store(true).build()?;
-        let auth = Arc::new(AuthManager::with_token(token, client.clone()));
-
-        Ok(Self {
-            chat_manager: ChatManager::new(client.clone(), auth.clone()),
-            file_uploader: FileUploader::new(client.clone(), auth.clone()),
-            media_generator: MediaGenerator::new(client.clone()),
-            media_downloader: MediaDownloader::new(client.clone()),
+        Self::builder().token(token).build()
+    }
+
+    pub(crate) fn from_parts(
+        http: Upstream,
+        auth: AuthManager,
+        events: Arc<dyn EventSink>,
+        video_poll: VideoPollConfig,
+        upload_limits: UploadLimits,
+    ) -> Self {
+        let auth = Arc::new(auth);
+
+        Self {
+            chat_manager: ChatManager::new(http.clone(), auth.clone()),
+            file_uploader: FileUploader::new(http.clone(), auth.clone()).with_limits(upload_limits),
+            media_generator: MediaGenerator::new(http.clone(), events.clone())
+                .with_poll_config(video_poll.clone()),
+            media_downloader: MediaDownloader::new(http.client.clone(), events.clone()),
             auth,
-            client,
-        })
+            http,
+            events,
+            video_poll,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.http.base_url()
+    }
+
+    /// Report streaming deltas and media progress to `events`.
+    /// Nothing is printed by default; pass `Arc::new(ConsoleSink)` to echo to stdout.
+    pub fn with_event_sink(mut self, events: Arc<dyn EventSink>) -> Self {
+        self.media_generator = MediaGenerator::new(self.http.clone(), events.clone())
+            .with_poll_config(self.video_poll.clone());
+        self.media_downloader = MediaDownloader::new(self.http.client.clone(), events.clone());
+        self.events = events;
+        self
+    }
+
+    /// Largest upload accepted per file class
+    pub fn with_upload_limits(mut self, limits: UploadLimits) -> Self {
+        self.file_uploader =
+            FileUploader::new(self.http.clone(), self.auth.clone()).with_limits(limits);
+        self
+    }
+
+    /// Poll interval, timeout and backoff cap used while waiting for videos
+    pub fn with_video_poll_config(mut self, video_poll: VideoPollConfig) -> Self {
+        self.media_generator = MediaGenerator::new(self.http.clone(), self.events.clone())
+            .with_poll_config(video_poll.clone());
+        self.video_poll = video_poll;
+        self
     }
 
     pub async fn get_models(&self) -> Result<Vec<Model>> {
@@ -115,6 +163,20 @@ impl QwenClient {
     }
 
     pub async fn select_model_for_files(&self, files: &[QwenFile]) -> Result<String> {
+        let (requires_vision, requires_audio, requires_video) = Self::file_requirements(files);
+
+        self.select_best_model(
+            requires_vision,
+            requires_audio,
+            requires_video,
+            false,
+            false,
+        )
+        .await
+    }
+
+    /// Which of vision, audio and video the files need
+    fn file_requirements(files: &[QwenFile]) -> (bool, bool, bool) {
         let mut requires_vision = false;
         let mut requires_audio = false;
         let mut requires_video = false;
@@ -128,14 +190,7 @@ impl QwenClient {
             }
         }
 
-        self.select_best_model(
-            requires_vision,
-            requires_audio,
-            requires_video,
-            false,
-            false,
-        )
-        .await
+        (requires_vision, requires_audio, requires_video)
     }
 
     pub async fn upload_file(&self, file_path: &str) -> Result<QwenFile> {
@@ -143,6 +198,37 @@ impl QwenClient {
         self.file_uploader.upload_file(file_path, user_id).await
     }
 
+    /// Upload in-memory data; only the last component of `filename` is used.
+    /// Passing `bytes::Bytes` lets a caller keep the data for a retry without copying it.
+    pub async fn upload_bytes(
+        &self,
+        filename: &str,
+        data: impl Into<bytes::Bytes>,
+    ) -> Result<QwenFile> {
+        let user_id = self.chat_manager.get_user_id().await?;
+        self.file_uploader
+            .upload_bytes(filename, data.into(), user_id)
+            .await
+    }
+
+    /// Stream `size` bytes from `reader` to Qwen without buffering the whole file
+    pub async fn upload_stream<R>(&self, filename: &str, reader: R, size: u64) -> Result<QwenFile>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+    {
+        let user_id = self.chat_manager.get_user_id().await?;
+        self.file_uploader
+            .upload_stream(filename, reader, size, user_id)
+            .await
+    }
+
+    /// How a file would be uploaded, judged from its name and first bytes
+    /// (up to [`SNIFF_LEN`](crate::qwen::client::modules::file_type::SNIFF_LEN));
+    /// `None` for types Qwen does not accept
+    pub fn file_kind(filename: &str, head: &[u8]) -> Option<FileKind> {
+        FileKind::detect(filename, head)
+    }
+
     pub async fn ask_question(&self, message: &str, model_id: Option<&str>) -> Result<String> {
         let response = self.start_convo(message, model_id, None).await?;
         Ok(response.content)
@@ -165,43 +251,187 @@ impl QwenClient {
         model_id: Option<&str>,
         extra_data: Option<&ExtraData>,
     ) -> Result<QwenResponse> {
-        let token = self.auth.get_token().await?;
+        self.send(ChatRequest {
+            model: model_id.map(|s| s.to_string()),
+            files,
+            extra_data: extra_data.cloned(),
+            ..ChatRequest::new(message, ChatType::TextToText)
+        })
+        .await
+    }
 
-        let model = if let Some(id) = model_id {
-            id.to_string()
-        } else if !files.is_empty() {
-            self.select_model_for_files(&files).await?
-        } else {
-            "qwen3-max".to_string()
-        };
+    /// Build a turn with files, search and thinking set independently:
+    ///
+    /// ```no_run
+    /// # async fn run(client: reverse_api::QwenClient) -> reverse_api::QwenResult<()> {
+    /// let report = client.upload_file("report.pdf").await?;
+    /// let response = client
+    ///     .request("Summarize this report and check the numbers against the web")
+    ///     .file(report)
+    ///     .search(true)
+    ///     .thinking(true)
+    ///     .thinking_budget(8192)
+    ///     .send()
+    ///     .await?;
+    /// println!("{}", response.content);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn request(&self, message: impl Into<String>) -> QwenRequestBuilder<'_> {
+        QwenRequestBuilder::new(self, message)
+    }
 
+    /// Send one turn in `request.chat_type` mode and collect the answer.
+    ///
+    /// Deep research reports each of its phases to the event sink as it goes.
+    /// Image and video generation go through `generate_image` / `generate_video`.
+    pub async fn send(&self, request: ChatRequest) -> Result<QwenResponse> {
+        if request.enable_thinking
+            && matches!(
+                request.chat_type,
+                ChatType::TextToImage | ChatType::TextToVideo
+            )
+        {
+            return Err(QwenError::InvalidRequest(format!(
+                "Thinking is not available for {}",
+                request.chat_type.as_str()
+            )));
+        }
+
+        match request.chat_type {
+            ChatType::TextToImage => {
+                return self
+                    .generate_image(
+                        &request.message,
+                        None,
+                        request.model.as_deref(),
+                        request.extra_data.as_ref(),
+                    )
+                    .await
+            }
+            ChatType::TextToVideo => {
+                return self
+                    .generate_video(
+                        &request.message,
+                        None,
+                        request.model.as_deref(),
+                        request.extra_data.as_ref(),
+                    )
+                    .await
+            }
+            _ => {}
+        }
+
+        let (stream, chat_id, parent_id) = self.open_completion(request).await?;
+
+        let output =
+            StreamingHandler::handle_streaming_response(stream, self.events.as_ref()).await?;
+
+        Ok(QwenResponse {
+            content: output.content,
+            response_id: output.response_id,
+            chat_id: Some(chat_id),
+            parent_id,
+            web_search_results: output.web_search_results,
+            thinking_content: output.thinking_content,
+            images: output.images,
+        })
+    }
+
+    /// Like [`send`](Self::send), but return the events as they arrive.
+    /// Video generation runs as a background task upstream and cannot be streamed.
+    pub async fn send_stream(&self, request: ChatRequest) -> Result<QwenEventStream> {
+        if request.chat_type == ChatType::TextToVideo {
+            return Err(QwenError::InvalidRequest(
+                "Video generation cannot be streamed, use generate_video".to_string(),
+            ));
+        }
+
+        let (stream, chat_id, _) = self.open_completion(request).await?;
+
+        let events = stream.map(move |event| match event {
+            Ok(QwenEvent::ResponseCreated {
+                response_id,
+                chat_id: created_chat_id,
+            }) if created_chat_id.is_empty() => Ok(QwenEvent::ResponseCreated {
+                response_id,
+                chat_id: chat_id.clone(),
+            }),
+            other => other,
+        });
+
+        Ok(Box::pin(events))
+    }
+
+    /// Start (or continue) a conversation and return its events as they arrive.
+    /// Pass the `response_id` from `ResponseCreated` as the next turn's parent id.
+    pub async fn stream_convo(
+        &self,
+        message: &str,
+        files: Vec<QwenFile>,
+        model_id: Option<&str>,
+        extra_data: Option<&ExtraData>,
+        enable_search: bool,
+    ) -> Result<QwenEventStream> {
+        self.send_stream(ChatRequest {
+            model: model_id.map(|s| s.to_string()),
+            files,
+            extra_data: extra_data.cloned(),
+            ..ChatRequest::new(message, ChatType::TextToText).with_search(enable_search)
+        })
+        .await
+    }
+
+    /// Send the completion request and return the events of its still-unread SSE
+    /// response together with the chat id and parent id it was sent under.
+    async fn open_completion(
+        &self,
+        request: ChatRequest,
+    ) -> Result<(QwenEventStream, String, Option<String>)> {
+        if request.chat_type == ChatType::ImageEdit
+            && !request.files.iter().any(|f| f.file_class == "vision")
+        {
+            return Err(QwenError::InvalidRequest(
+                "Image editing needs an uploaded source image".to_string(),
+            ));
+        }
+
+        let token = self.auth.get_token().await?;
+        let (model, feature_config) = self.resolve_model(&request).await?;
+
+        let extra_data = request.extra_data.as_ref();
         let chat_id = if let Some(data) = extra_data {
             data.chat_id.clone()
         } else {
             self.chat_manager.create_or_get_chat(&model).await?
         };
 
-        let url = format!(
-            "{}/api/v2/chat/completions?chat_id={}",
-            super::modules::constants::BASE_URL,
-            chat_id
-        );
-        let headers = build_json_headers(Some(&token));
+        let url = self
+            .http
+            .url(&format!("/api/v2/chat/completions?chat_id={}", chat_id));
+        let headers = self.http.json_headers(Some(&token));
 
         let parent_id = extra_data.and_then(|d| d.parent_id.clone());
 
-        let completion_request = ConversationBuilder::build_completion_request(
-            message,
+        let completion_request = ConversationBuilder::build_request(
+            &request.message,
             &model,
-            files,
+            request.files,
             chat_id.clone(),
             parent_id.clone(),
-            false,
-            false,
-            None,
+            request.chat_type.as_str(),
+            feature_config,
+        );
+
+        let recorded = RecordedRequest::new(
+            "POST",
+            &url,
+            &headers,
+            serde_json::to_value(&completion_request).ok(),
         );
 
         let response = self
+            .http
             .client
             .post(&url)
             .headers(headers)
@@ -209,25 +439,72 @@ impl QwenClient {
             .send()
             .await?;
 
+        // Requests refused before streaming starts get a JSON body, sometimes with a 200
         let status = response.status();
-        if !status.is_success() {
+        let is_json = response
+            .headers()
+            .get(rquest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/json"));
+        if !status.is_success() || is_json {
+            let headers = response.headers().clone();
             let error_text = response.text().await?;
-            return Err(crate::qwen::error::QwenError::ApiError(format!(
-                "Chat completion failed ({}): {}",
-                status, error_text
-            )));
+            return Err(QwenError::from_response(
+                "Chat completion",
+                status,
+                &headers,
+                &error_text,
+            ));
         }
 
-        let output = StreamingHandler::handle_streaming_response(response).await?;
+        let head = RecordedResponse::head(status.as_u16(), response.headers());
+        let body =
+            self.http
+                .recorder
+                .tap("qwen-completion", recorded, head, response.bytes_stream());
 
-        Ok(QwenResponse {
-            content: output.content,
-            response_id: output.response_id,
-            chat_id: Some(chat_id),
-            parent_id,
-            web_search_results: output.web_search_results,
-            thinking_content: output.thinking_content,
-        })
+        Ok((StreamingHandler::decode(body), chat_id, parent_id))
+    }
+
+    /// Pick the model for a turn and check its thinking settings.
+    /// The model list is only fetched when files or thinking need it.
+    async fn resolve_model(&self, request: &ChatRequest) -> Result<(String, FeatureConfig)> {
+        let auto_select = request.model.is_none() && !request.files.is_empty();
+        let models = if auto_select || request.enable_thinking {
+            self.get_models().await?
+        } else {
+            vec![]
+        };
+
+        let model = match &request.model {
+            Some(id) => id.clone(),
+            None if auto_select => {
+                let (requires_vision, requires_audio, requires_video) =
+                    Self::file_requirements(&request.files);
+                ModelSelector::select_best_model(
+                    models.clone(),
+                    requires_vision,
+                    requires_audio,
+                    requires_video,
+                    request.enable_thinking,
+                    request.chat_type == ChatType::Search,
+                )
+            }
+            None => "qwen3-max".to_string(),
+        };
+
+        // Fail early instead of letting the server silently answer without thinking
+        let thinking_budget = if request.enable_thinking {
+            ModelSelector::validate_thinking(&models, &model, request.thinking_budget)?
+        } else {
+            None
+        };
+
+        Ok((
+            model,
+            FeatureConfig::new(request.enable_thinking, thinking_budget)
+                .for_chat_type(request.chat_type),
+        ))
     }
 
     pub async fn continue_convo(
@@ -254,8 +531,12 @@ impl QwenClient {
         model_id: Option<&str>,
         extra_data: Option<&ExtraData>,
     ) -> Result<QwenResponse> {
-        self.start_convo_with_options(message, model_id, extra_data, true, false, None)
-            .await
+        self.send(ChatRequest {
+            model: Some(model_id.unwrap_or("qwen3-max").to_string()),
+            extra_data: extra_data.cloned(),
+            ..ChatRequest::new(message, ChatType::Search)
+        })
+        .await
     }
 
     pub async fn start_convo_with_thinking(
@@ -265,75 +546,14 @@ impl QwenClient {
         extra_data: Option<&ExtraData>,
         thinking_budget: Option<u32>,
     ) -> Result<QwenResponse> {
-        self.start_convo_with_options(message, model_id, extra_data, false, true, thinking_budget)
-            .await
-    }
-
-    async fn start_convo_with_options(
-        &self,
-        message: &str,
-        model_id: Option<&str>,
-        extra_data: Option<&ExtraData>,
-        enable_search: bool,
-        enable_thinking: bool,
-        thinking_budget: Option<u32>,
-    ) -> Result<QwenResponse> {
-        let token = self.auth.get_token().await?;
-
-        let model = model_id.unwrap_or("qwen3-max");
-        let chat_id = if let Some(data) = extra_data {
-            data.chat_id.clone()
-        } else {
-            self.chat_manager.create_or_get_chat(model).await?
-        };
-
-        let url = format!(
-            "{}/api/v2/chat/completions?chat_id={}",
-            super::modules::constants::BASE_URL,
-            chat_id
-        );
-        let headers = build_json_headers(Some(&token));
-
-        let parent_id = extra_data.and_then(|d| d.parent_id.clone());
-
-        let completion_request = ConversationBuilder::build_completion_request(
-            message,
-            model,
-            vec![],
-            chat_id.clone(),
-            parent_id.clone(),
-            enable_search,
-            enable_thinking,
+        self.send(ChatRequest {
+            model: Some(model_id.unwrap_or("qwen3-max").to_string()),
+            enable_thinking: true,
             thinking_budget,
-        );
-
-        let response = self
-            .client
-            .post(&url)
-            .headers(headers)
-            .json(&completion_request)
-            .send()
-            .await?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await?;
-            return Err(crate::qwen::error::QwenError::ApiError(format!(
-                "Chat completion failed ({}): {}",
-                status, error_text
-            )));
-        }
-
-        let output = StreamingHandler::handle_streaming_response(response).await?;
-
-        Ok(QwenResponse {
-            content: output.content,
-            response_id: output.response_id,
-            chat_id: Some(chat_id),
-            parent_id: None,
-            web_search_results: output.web_search_results,
-            thinking_content: output.thinking_content,
+            extra_data: extra_data.cloned(),
+            ..ChatRequest::new(message, ChatType::TextToText)
         })
+        .await
     }
 
     // ============================================================
@@ -361,6 +581,71 @@ impl QwenClient {
             .await
     }
 
+    /// Generate `n` images for one prompt, collected in `images`.
+    ///
+    /// The first turn continues `extra_data`; while fewer than `n` URLs were returned,
+    /// more turns run in fresh chats. Ids in the response are those of the first turn.
+    pub async fn generate_images(
+        &self,
+        prompt: &str,
+        n: usize,
+        size: Option<&str>,
+        model_id: Option<&str>,
+        extra_data: Option<&ExtraData>,
+    ) -> Result<QwenResponse> {
+        if n == 0 {
+            return Err(QwenError::InvalidRequest(
+                "At least one image must be requested".to_string(),
+            ));
+        }
+
+        let mut response = self
+            .generate_image(prompt, size, model_id, extra_data)
+            .await?;
+        let mut images = std::mem::take(&mut response.images);
+
+        while images.len() < n {
+            let more = self.generate_image(prompt, size, model_id, None).await?;
+            let before = images.len();
+            for url in more.images {
+                if !images.contains(&url) {
+                    images.push(url);
+                }
+            }
+            if images.len() == before {
+                return Err(QwenError::ApiError(
+                    "Image generation returned no new image".to_string(),
+                ));
+            }
+        }
+
+        images.truncate(n);
+        response.content = images[0].clone();
+        response.images = images;
+        Ok(response)
+    }
+
+    /// Edit an uploaded image (see [`upload_file`](Self::upload_file)) following `prompt`
+    pub async fn edit_image(
+        &self,
+        prompt: &str,
+        source: QwenFile,
+        size: Option<&str>,
+        model_id: Option<&str>,
+    ) -> Result<QwenResponse> {
+        if source.file_class != "vision" {
+            return Err(QwenError::InvalidRequest(format!(
+                "Image editing needs an image, got a {} file",
+                source.file_class
+            )));
+        }
+
+        let token = self.auth.get_token().await?;
+        self.media_generator
+            .edit_image(prompt, source, size, model_id, &token, &self.chat_manager)
+            .await
+    }
+
     /// Generate a video from text prompt
     pub async fn generate_video(
         &self,
@@ -382,7 +667,89 @@ impl QwenClient {
             .await
     }
 
-    /// Generate a video with progress callback
+    /// Submit a video task without waiting for it; poll it with [`video_status`](Self::video_status)
+    pub async fn start_video(
+        &self,
+        prompt: &str,
+        size: Option<&str>,
+        model_id: Option<&str>,
+        extra_data: Option<&ExtraData>,
+    ) -> Result<VideoTask> {
+        self.start_video_request(&VideoRequest {
+            prompt: prompt.to_string(),
+            size: size.map(|s| s.to_string()),
+            model: model_id.map(|m| m.to_string()),
+            first_frame: None,
+            extra_data: extra_data.cloned(),
+        })
+        .await
+    }
+
+    /// Submit a text-to-video or, with `first_frame`, an image-to-video task without waiting for it
+    pub async fn start_video_request(&self, request: &VideoRequest) -> Result<VideoTask> {
+        if let Some(frame) = &request.first_frame {
+            if frame.file_class != "vision" {
+                return Err(QwenError::InvalidRequest(format!(
+                    "The first frame must be an image, got a {} file",
+                    frame.file_class
+                )));
+            }
+        }
+
+        let token = self.auth.get_token().await?;
+        self.media_generator
+            .start_video_task(request, &token, &self.chat_manager)
+            .await
+    }
+
+    /// Generate a video starting from an uploaded image (see [`upload_file`](Self::upload_file))
+    pub async fn generate_video_from_image(
+        &self,
+        prompt: &str,
+        first_frame: QwenFile,
+        size: Option<&str>,
+        model_id: Option<&str>,
+    ) -> Result<QwenResponse> {
+        let task = self
+            .start_video_request(&VideoRequest {
+                prompt: prompt.to_string(),
+                size: size.map(|s| s.to_string()),
+                model: model_id.map(|m| m.to_string()),
+                first_frame: Some(first_frame),
+                extra_data: None,
+            })
+            .await?;
+        let video_url = self.wait_for_video(&task.task_id, |_| {}).await?;
+
+        Ok(QwenResponse {
+            content: video_url,
+            response_id: task.response_id,
+            chat_id: Some(task.chat_id),
+            parent_id: task.parent_id,
+            web_search_results: None,
+            thinking_content: None,
+            images: vec![],
+        })
+    }
+
+    /// Current state of a video task: `running`, `success` (with the URL in `content`) or `failed`
+    pub async fn video_status(&self, task_id: &str) -> Result<TaskStatus> {
+        let token = self.auth.get_token().await?;
+        self.media_generator.task_status(task_id, &token).await
+    }
+
+    /// Poll a task from [`start_video`](Self::start_video) until it finishes and return the video URL
+    pub async fn wait_for_video<F>(&self, task_id: &str, progress_callback: F) -> Result<String>
+    where
+        F: Fn(&VideoProgress) + Send + Sync,
+    {
+        let token = self.auth.get_token().await?;
+        self.media_generator
+            .wait_for_video(task_id, &token, progress_callback)
+            .await
+    }
+
+    /// Generate a video, reporting every poll to `progress_callback`
     pub async fn generate_video_with_progress<F>(
         &self,
         prompt: &str,
@@ -392,7 +759,7 @@ impl QwenClient {
         progress_callback: F,
     ) -> Result<QwenResponse>
     where
-        F: Fn(&str, u8) + Send + Sync,
+        F: Fn(&VideoProgress) + Send + Sync,
     {
         let token = self.auth.get_token().await?;
         self.media_generator
@@ -412,4 +779,9 @@ impl QwenClient {
     pub async fn download_media(&self, url: &str, output_path: &str) -> Result<()> {
         self.media_downloader.download_media(url, output_path).await
     }
+
+    /// Fetch media (image or video) into memory
+    pub async fn fetch_media(&self, url: &str) -> Result<Vec<u8>> {
+        self.media_downloader.fetch_media(url).await
+    }
 }
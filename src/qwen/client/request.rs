@@ -0,0 +1,73 @@
+use super::modules::streaming::QwenEventStream;
+use super::qwen::QwenClient;
+use crate::qwen::error::Result;
+use crate::qwen::models::{ChatRequest, ChatType, ExtraData, QwenFile, QwenResponse};
+
+/// Builder returned by [`QwenClient::request`]; every option is independent of the others
+pub struct QwenRequestBuilder<'a> {
+    client: &'a QwenClient,
+    request: ChatRequest,
+}
+
+impl<'a> QwenRequestBuilder<'a> {
+    pub(crate) fn new(client: &'a QwenClient, message: impl Into<String>) -> Self {
+        Self {
+            client,
+            request: ChatRequest::new(message, ChatType::TextToText),
+        }
+    }
+
+    pub fn chat_type(mut self, chat_type: ChatType) -> Self {
+        self.request.chat_type = chat_type;
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.request = self.request.with_model(model);
+        self
+    }
+
+    /// Attach one uploaded file
+    pub fn file(mut self, file: QwenFile) -> Self {
+        self.request.files.push(file);
+        self
+    }
+
+    pub fn files(mut self, files: impl IntoIterator<Item = QwenFile>) -> Self {
+        self.request.files.extend(files);
+        self
+    }
+
+    pub fn search(mut self, enable_search: bool) -> Self {
+        self.request = self.request.with_search(enable_search);
+        self
+    }
+
+    pub fn thinking(mut self, enable_thinking: bool) -> Self {
+        self.request = self.request.with_thinking(enable_thinking);
+        self
+    }
+
+    pub fn thinking_budget(mut self, budget: u32) -> Self {
+        self.request = self.request.with_thinking_budget(budget);
+        self
+    }
+
+    /// Continue the conversation `extra_data` points at
+    pub fn continue_from(mut self, extra_data: ExtraData) -> Self {
+        self.request = self.request.with_extra_data(extra_data);
+        self
+    }
+
+    pub fn build(self) -> ChatRequest {
+        self.request
+    }
+
+    pub async fn send(self) -> Result<QwenResponse> {
+        self.client.send(self.request).await
+    }
+
+    pub async fn stream(self) -> Result<QwenEventStream> {
+        self.client.send_stream(self.request).await
+    }
+}
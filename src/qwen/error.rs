@@ -1,8 +1,28 @@
+use rquest::header::HeaderMap;
+use rquest::StatusCode;
 use std::fmt;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub enum QwenError {
+    /// Upstream failure that none of the variants below describes
     ApiError(String),
+    /// The request was rejected locally before being sent (e.g. unsupported model option),
+    /// or upstream rejected it as malformed
+    InvalidRequest(String),
+    /// The token is missing, expired or was revoked
+    Unauthorized(String),
+    /// Too many requests for this token; `retry_after` comes from the `Retry-After` header
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The prompt or the generated content was blocked by content moderation
+    ContentFiltered(String),
+    /// The model, chat or task named in the request does not exist
+    ModelNotFound(String),
+    /// Upstream failed with a 5xx or reported an internal error
+    UpstreamUnavailable(String),
     NetworkError(rquest::Error),
     ReqwestError(reqwest::Error),
     JsonError(serde_json::Error),
@@ -10,10 +30,104 @@ pub enum QwenError {
     IoError(std::io::Error),
 }
 
+impl QwenError {
+    /// Error for a failed HTTP response, classified by the `code` in its JSON body and
+    /// by its status when the code is missing or unknown
+    pub fn from_response(
+        action: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Self {
+        let message = format!("{} failed ({}): {}", action, status, body);
+        let code = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|json| {
+                [&json["data"]["code"], &json["error"]["code"], &json["code"]]
+                    .into_iter()
+                    .find_map(|code| code.as_str().map(str::to_string))
+            });
+        let retry_after = headers
+            .get(rquest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+
+        match code
+            .as_deref()
+            .and_then(|code| Self::classify(code, message.clone()))
+        {
+            Some(QwenError::RateLimited { message, .. }) => QwenError::RateLimited {
+                message,
+                retry_after,
+            },
+            Some(error) => error,
+            None => match status.as_u16() {
+                401 | 403 => QwenError::Unauthorized(message),
+                429 => QwenError::RateLimited {
+                    message,
+                    retry_after,
+                },
+                404 => QwenError::ModelNotFound(message),
+                400 | 422 => QwenError::InvalidRequest(message),
+                500..=599 => QwenError::UpstreamUnavailable(message),
+                _ => QwenError::ApiError(message),
+            },
+        }
+    }
+
+    /// Error for an `error` event inside a completion stream
+    pub fn from_event(code: &str, details: &str) -> Self {
+        let message = format!("Server error: {} - {}", code, details);
+        Self::classify(code, message.clone()).unwrap_or(QwenError::ApiError(message))
+    }
+
+    /// Map an upstream error code such as `RateLimited` or `Bad_Request` to a variant
+    fn classify(code: &str, message: String) -> Option<Self> {
+        let code: String = code
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let error = match code.as_str() {
+            "unauthorized" | "tokenexpired" | "invalidtoken" | "forbidden" => {
+                QwenError::Unauthorized(message)
+            }
+            "ratelimited" | "toomanyrequests" | "quotaexceeded" | "throttling" => {
+                QwenError::RateLimited {
+                    message,
+                    retry_after: None,
+                }
+            }
+            "datainspectionfailed"
+            | "contentfiltered"
+            | "sensitivecontent"
+            | "inappropriatecontent" => QwenError::ContentFiltered(message),
+            "modelnotfound" | "notfound" | "chatnotfound" | "chatnotexist" => {
+                QwenError::ModelNotFound(message)
+            }
+            "internalerror" | "serviceunavailable" | "badgateway" | "timeout" => {
+                QwenError::UpstreamUnavailable(message)
+            }
+            "badrequest" | "invalidparameter" | "invalidrequest" => {
+                QwenError::InvalidRequest(message)
+            }
+            _ => return None,
+        };
+        Some(error)
+    }
+}
+
 impl fmt::Display for QwenError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             QwenError::ApiError(msg) => write!(f, "API Error: {}", msg),
+            QwenError::InvalidRequest(msg) => write!(f, "Invalid Request: {}", msg),
+            QwenError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            QwenError::RateLimited { message, .. } => write!(f, "Rate Limited: {}", message),
+            QwenError::ContentFiltered(msg) => write!(f, "Content Filtered: {}", msg),
+            QwenError::ModelNotFound(msg) => write!(f, "Not Found: {}", msg),
+            QwenError::UpstreamUnavailable(msg) => write!(f, "Upstream Unavailable: {}", msg),
             QwenError::NetworkError(e) => write!(f, "Network Error: {}", e),
             QwenError::ReqwestError(e) => write!(f, "Reqwest Error: {}", e),
             QwenError::JsonError(e) => write!(f, "JSON Error: {}", e),
@@ -56,3 +170,74 @@ impl From<reqwest::Error> for QwenError {
 }
 
 pub type Result<T> = std::result::Result<T, QwenError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rquest::header::HeaderValue;
+
+    #[test]
+    fn responses_are_classified_by_code_then_status() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+
+        let limited = QwenError::from_response(
+            "Chat completion",
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers,
+            r#"{"success":false,"data":{"code":"RateLimited","details":"slow down"}}"#,
+        );
+        assert!(matches!(
+            limited,
+            QwenError::RateLimited { retry_after: Some(d), .. } if d == Duration::from_secs(7)
+        ));
+
+        let filtered = QwenError::from_response(
+            "Chat completion",
+            StatusCode::OK,
+            &HeaderMap::new(),
+            r#"{"error":{"code":"data_inspection_failed"}}"#,
+        );
+        assert!(matches!(filtered, QwenError::ContentFiltered(_)));
+
+        let by_status =
+            |status, body| QwenError::from_response("Get models", status, &HeaderMap::new(), body);
+        assert!(matches!(
+            by_status(StatusCode::UNAUTHORIZED, "nope"),
+            QwenError::Unauthorized(_)
+        ));
+        assert!(matches!(
+            by_status(StatusCode::NOT_FOUND, ""),
+            QwenError::ModelNotFound(_)
+        ));
+        assert!(matches!(
+            by_status(StatusCode::BAD_GATEWAY, "<html>"),
+            QwenError::UpstreamUnavailable(_)
+        ));
+        assert!(matches!(
+            by_status(StatusCode::IM_A_TEAPOT, r#"{"code":"Mystery"}"#),
+            QwenError::ApiError(_)
+        ));
+    }
+
+    #[test]
+    fn stream_error_codes_are_classified() {
+        assert!(matches!(
+            QwenError::from_event("Bad_Request", "bad"),
+            QwenError::InvalidRequest(_)
+        ));
+        assert!(matches!(
+            QwenError::from_event("TokenExpired", ""),
+            QwenError::Unauthorized(_)
+        ));
+        assert!(matches!(
+            QwenError::from_event("Internal_Error", ""),
+            QwenError::UpstreamUnavailable(_)
+        ));
+        let unknown = QwenError::from_event("Something", "else");
+        assert_eq!(
+            unknown.to_string(),
+            "API Error: Server error: Something - else"
+        );
+    }
+}
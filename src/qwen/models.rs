@@ -1,26 +1,36 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Chat type for different interaction modes
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChatType {
     /// Normal text-to-text conversation
     #[default]
+    #[serde(rename = "t2t")]
     TextToText,
     /// Web search enabled
+    #[serde(rename = "search")]
     Search,
     /// Deep research mode
+    #[serde(rename = "deep_research")]
     DeepResearch,
     /// Image editing
+    #[serde(rename = "image_edit")]
     ImageEdit,
     /// Text to video
+    #[serde(rename = "t2v")]
     TextToVideo,
-    /// Text to image  
+    /// Text to image
+    #[serde(rename = "t2i")]
     TextToImage,
     /// Web development mode
+    #[serde(rename = "web_dev")]
     WebDev,
     /// Artifacts mode
+    #[serde(rename = "artifacts")]
     Artifacts,
     /// Travel planning
+    #[serde(rename = "travel")]
     Travel,
 }
 
@@ -40,6 +50,70 @@ impl ChatType {
     }
 }
 
+/// One turn for [`QwenClient::send`](crate::QwenClient::send).
+///
+/// Files, search and thinking are independent, so one turn can read a document,
+/// search the web and think before answering.
+#[derive(Debug, Clone, Default)]
+pub struct ChatRequest {
+    pub message: String,
+    pub chat_type: ChatType,
+    /// Defaults to a model that fits `files` (and thinking), or `qwen3-max`
+    pub model: Option<String>,
+    /// Uploaded files; `ImageEdit` needs the source image here
+    pub files: Vec<QwenFile>,
+    pub enable_thinking: bool,
+    /// Checked against the model's limit; ignored unless thinking is enabled
+    pub thinking_budget: Option<u32>,
+    /// Continue an earlier conversation
+    pub extra_data: Option<ExtraData>,
+}
+
+impl ChatRequest {
+    pub fn new(message: impl Into<String>, chat_type: ChatType) -> Self {
+        Self {
+            message: message.into(),
+            chat_type,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_files(mut self, files: Vec<QwenFile>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Switch between plain chat and web search
+    pub fn with_search(mut self, enable_search: bool) -> Self {
+        self.chat_type = if enable_search {
+            ChatType::Search
+        } else {
+            ChatType::TextToText
+        };
+        self
+    }
+
+    pub fn with_thinking(mut self, enable_thinking: bool) -> Self {
+        self.enable_thinking = enable_thinking;
+        self
+    }
+
+    pub fn with_thinking_budget(mut self, budget: u32) -> Self {
+        self.thinking_budget = Some(budget);
+        self
+    }
+
+    pub fn with_extra_data(mut self, extra_data: ExtraData) -> Self {
+        self.extra_data = Some(extra_data);
+        self
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Message {
     pub role: String,
@@ -251,6 +325,34 @@ pub struct FeatureConfig {
     pub thinking_budget: Option<u32>,
 }
 
+impl FeatureConfig {
+    /// Feature flags for a turn; the budget is only sent when thinking is enabled
+    pub fn new(thinking_enabled: bool, thinking_budget: Option<u32>) -> Self {
+        Self {
+            thinking_enabled,
+            output_schema: "phase".to_string(),
+            research_mode: "normal".to_string(),
+            thinking_budget: thinking_budget.filter(|_| thinking_enabled),
+        }
+    }
+
+    /// Deep research runs in the `advance` research mode, every other chat type in `normal`
+    pub fn for_chat_type(mut self, chat_type: ChatType) -> Self {
+        self.research_mode = match chat_type {
+            ChatType::DeepResearch => "advance",
+            _ => "normal",
+        }
+        .to_string();
+        self
+    }
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self::new(false, None)
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct Extra {
     pub meta: Meta,
@@ -341,6 +443,28 @@ pub struct QwenResponse {
     pub web_search_results: Option<Vec<WebSearchInfo>>, // Web search results if search was enabled
     #[serde(default)]
     pub thinking_content: Option<String>, // Thinking process if thinking was enabled
+    #[serde(default)]
+    pub images: Vec<String>, // Every image URL produced in this turn, in order
+}
+
+// Incremental events produced while a completion is streamed
+#[derive(Debug, Clone)]
+pub enum QwenEvent {
+    ResponseCreated {
+        response_id: String,
+        chat_id: String,
+    },
+    /// The upstream moved to another phase, e.g. `thinking`, `web_search` or a deep research step
+    PhaseChanged(String),
+    ThinkingDelta(String),
+    AnswerDelta(String),
+    WebSearchResults(Vec<WebSearchInfo>),
+    ImageGenerated(String), // URL of the generated image
+    Error {
+        code: String,
+        details: String,
+    },
+    Done,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -380,6 +504,101 @@ pub struct WanxTask {
     pub task_id: String,
 }
 
+/// A video generation task accepted upstream; poll it with `QwenClient::video_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoTask {
+    pub task_id: String,
+    pub chat_id: String,
+    /// Id of the message the video belongs to
+    pub response_id: String,
+    pub parent_id: Option<String>,
+}
+
+/// Where a video task stands, reported on every poll
+#[derive(Debug, Clone)]
+pub struct VideoProgress {
+    /// Upstream task status: `running`, `success`, `failed`, ...
+    pub status: String,
+    pub message: String,
+    /// Upstream estimate of the time left, when it sent one we could read
+    pub remaining: Option<Duration>,
+    /// Time since the task was submitted
+    pub elapsed: Duration,
+}
+
+/// How video tasks are polled
+#[derive(Debug, Clone)]
+pub struct VideoPollConfig {
+    /// Delay between polls while the task is running
+    pub interval: Duration,
+    /// Give up once the task has been polled this long
+    pub timeout: Duration,
+    /// Upper bound for the delay after failed polls, which doubles each time
+    pub max_backoff: Duration,
+}
+
+impl Default for VideoPollConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(5 * 60),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Largest file accepted per file class, in bytes
+#[derive(Debug, Clone)]
+pub struct UploadLimits {
+    pub vision: u64,
+    pub video: u64,
+    pub audio: u64,
+    pub document: u64,
+}
+
+impl UploadLimits {
+    /// Limit for a `file_class` as reported on [`QwenFile`]
+    pub fn max_for(&self, file_class: &str) -> u64 {
+        match file_class {
+            "vision" => self.vision,
+            "video" => self.video,
+            "audio" => self.audio,
+            _ => self.document,
+        }
+    }
+
+    /// The highest limit of any class
+    pub fn largest(&self) -> u64 {
+        self.vision
+            .max(self.video)
+            .max(self.audio)
+            .max(self.document)
+    }
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        const MIB: u64 = 1024 * 1024;
+        Self {
+            vision: 20 * MIB,
+            video: 500 * MIB,
+            audio: 100 * MIB,
+            document: 100 * MIB,
+        }
+    }
+}
+
+/// A video task to submit; `first_frame` turns it into image-to-video
+#[derive(Debug, Clone, Default)]
+pub struct VideoRequest {
+    pub prompt: String,
+    pub size: Option<String>,
+    pub model: Option<String>,
+    /// Uploaded image the video starts from
+    pub first_frame: Option<QwenFile>,
+    pub extra_data: Option<ExtraData>,
+}
+
 // Task status polling response
 #[derive(Debug, Deserialize, Clone)]
 pub struct TaskStatus {
@@ -0,0 +1,315 @@
+//! Upstream HTTP exchanges captured as fixture files, and replayed offline.
+//!
+//! With `REVERSE_API_RECORD_DIR` set, every streamed completion the clients read is
+//! written to that directory as one JSON file per exchange. Credentials in request
+//! headers, request bodies and response headers are replaced with `[redacted]` before
+//! anything reaches the disk; response bodies are kept byte for byte since they are
+//! what the parsers are tested against. [`Exchange::replay`] turns a fixture back into
+//! the chunked byte stream the parsers normally read from the network.
+
+use futures_util::stream::{Stream, StreamExt};
+use rquest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory recordings are written to; recording is off when unset
+pub const RECORD_DIR_ENV: &str = "REVERSE_API_RECORD_DIR";
+
+const REDACTED: &str = "[redacted]";
+
+/// Headers carrying credentials, signatures or session cookies
+const SECRET_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "set-cookie",
+    "x-ds-pow-response",
+    "x-statsig-id",
+    "x-oss-security-token",
+];
+
+/// JSON fields carrying credentials, at any depth of a request body
+const SECRET_FIELDS: &[&str] = &[
+    "token",
+    "access_token",
+    "refresh_token",
+    "password",
+    "access_key_id",
+    "access_key_secret",
+    "security_token",
+    "private_key",
+];
+
+/// One request and the response it got, as stored in a fixture file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+impl RecordedRequest {
+    /// Snapshot of a request with its credentials redacted
+    pub fn new(method: &str, url: &str, headers: &HeaderMap, body: Option<Value>) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: redact_headers(headers),
+            body: body.map(|mut body| {
+                redact_json(&mut body);
+                body
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The body exactly as received; invalid UTF-8 is replaced
+    #[serde(default)]
+    pub body: String,
+}
+
+impl RecordedResponse {
+    /// Status line and headers of a response whose body has not been read yet
+    pub fn head(status: u16, headers: &HeaderMap) -> Self {
+        Self {
+            status,
+            headers: redact_headers(headers),
+            body: String::new(),
+        }
+    }
+}
+
+impl Exchange {
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// The response body as a stream of `chunk_size` byte chunks, the last one shorter.
+    /// A `chunk_size` of 0 yields the whole body at once.
+    pub fn replay(
+        &self,
+        chunk_size: usize,
+    ) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send + 'static {
+        let body = self.response.body.as_bytes();
+        let chunks: Vec<std::io::Result<Vec<u8>>> = if chunk_size == 0 {
+            vec![Ok(body.to_vec())]
+        } else {
+            body.chunks(chunk_size).map(|c| Ok(c.to_vec())).collect()
+        };
+        futures_util::stream::iter(chunks)
+    }
+}
+
+/// Writes exchanges to a fixture directory, or does nothing when recording is off
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    dir: Option<PathBuf>,
+}
+
+impl Recorder {
+    /// Record to the directory named by [`RECORD_DIR_ENV`], if it is set
+    pub fn from_env() -> Self {
+        Self {
+            dir: std::env::var_os(RECORD_DIR_ENV)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+
+    pub fn to_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Write `exchange` as `<name>-<timestamp>.json`, returning the path written
+    pub fn save(&self, name: &str, exchange: &Exchange) -> std::io::Result<Option<PathBuf>> {
+        let Some(dir) = &self.dir else {
+            return Ok(None);
+        };
+        std::fs::create_dir_all(dir)?;
+
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let suffix = &uuid::Uuid::new_v4().simple().to_string()[..8];
+        let path = dir.join(format!("{}-{}-{}.json", name, millis, suffix));
+
+        std::fs::write(&path, serde_json::to_string_pretty(exchange)?)?;
+        Ok(Some(path))
+    }
+
+    /// Pass `body` through unchanged while keeping a copy of it. The exchange is saved
+    /// once the stream is dropped, so a body abandoned half way (say after an upstream
+    /// error event) is recorded as far as it was read. Recording never fails the call.
+    pub fn tap<S, B, E>(
+        &self,
+        name: &str,
+        request: RecordedRequest,
+        response: RecordedResponse,
+        body: S,
+    ) -> Pin<Box<dyn Stream<Item = Result<B, E>> + Send>>
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: AsRef<[u8]> + Send + 'static,
+        E: Send + 'static,
+    {
+        if !self.is_enabled() {
+            return Box::pin(body);
+        }
+
+        let mut capture = Capture {
+            recorder: self.clone(),
+            name: name.to_string(),
+            exchange: Exchange { request, response },
+            raw: Vec::new(),
+        };
+        Box::pin(body.inspect(move |chunk| {
+            if let Ok(bytes) = chunk {
+                capture.raw.extend_from_slice(bytes.as_ref());
+            }
+        }))
+    }
+}
+
+/// Body bytes read so far; written out when the stream holding it goes away
+struct Capture {
+    recorder: Recorder,
+    name: String,
+    exchange: Exchange,
+    raw: Vec<u8>,
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        self.exchange.response.body = String::from_utf8_lossy(&self.raw).into_owned();
+        let _ = self.recorder.save(&self.name, &self.exchange);
+    }
+}
+
+fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = name.as_str().to_ascii_lowercase();
+            let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// A fixture from `tests/fixtures`, e.g. `fixture("qwen/thinking.json")`
+#[cfg(test)]
+pub(crate) fn fixture(name: &str) -> Exchange {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    Exchange::load(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rquest::header::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn credentials_are_redacted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", HeaderValue::from_static("Bearer secret"));
+        headers.insert("Cookie", HeaderValue::from_static("session=secret"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+
+        let request = RecordedRequest::new(
+            "POST",
+            "https://example.com/api",
+            &headers,
+            Some(
+                json!({ "email": "a@b.c", "password": "secret", "nested": [{ "token": "secret" }] }),
+            ),
+        );
+
+        assert_eq!(request.headers["authorization"], REDACTED);
+        assert_eq!(request.headers["cookie"], REDACTED);
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(
+            request.body.unwrap(),
+            json!({ "email": "a@b.c", "password": REDACTED, "nested": [{ "token": REDACTED }] })
+        );
+    }
+
+    #[tokio::test]
+    async fn tapped_body_round_trips_through_a_fixture() {
+        let dir = std::env::temp_dir().join(format!("recording-{}", uuid::Uuid::new_v4()));
+        let recorder = Recorder::to_dir(&dir);
+        let body = "data: {\"a\":1}\n\ndata: {\"b\":\"é\"}\n\n";
+        let original = Exchange {
+            request: RecordedRequest::default(),
+            response: RecordedResponse {
+                status: 200,
+                body: body.to_string(),
+                ..Default::default()
+            },
+        };
+
+        // Chunks of 5 bytes split the two-byte é; the recording must not
+        let tapped = recorder.tap(
+            "test",
+            original.request.clone(),
+            RecordedResponse::head(200, &HeaderMap::new()),
+            original.replay(5),
+        );
+        let read: Vec<u8> = tapped.map(|chunk| chunk.unwrap()).concat().await;
+        assert_eq!(read, body.as_bytes());
+
+        let written = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap();
+        let recorded = Exchange::load(written.path()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(recorded.response.status, 200);
+        assert_eq!(recorded.response.body, body);
+    }
+}
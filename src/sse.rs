@@ -0,0 +1,276 @@
+//! Server-sent events framing shared by the streaming clients.
+//!
+//! Network chunks end wherever the connection happens to split them: in the middle of
+//! a line, or in the middle of a multi-byte character. [`SseDecoder`] keeps the
+//! unfinished tail between chunks and only decodes complete lines, so the events it
+//! yields do not depend on chunk boundaries. Lines may end in `\n`, `\r\n` or `\r`;
+//! `event:`, `id:` and multi-line `data:` fields are handled as the HTML spec describes.
+
+use futures_util::stream::{Stream, StreamExt};
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// One dispatched event. `data` lines are joined with `\n`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event:` field, if the event had one
+    pub event: Option<String>,
+    /// The last `id:` seen on the stream so far
+    pub id: Option<String>,
+    pub data: String,
+}
+
+/// Incremental decoder: feed it chunks as they arrive, then call [`finish`](Self::finish)
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    started: bool,
+    event: Option<String>,
+    id: Option<String>,
+    data: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a chunk of the body, returning the events it completed
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        if !self.started {
+            // A byte order mark may only appear at the very start of the body
+            if self.buffer.len() < BOM.len() && BOM.starts_with(&self.buffer) {
+                return Vec::new();
+            }
+            self.started = true;
+            if self.buffer.starts_with(BOM) {
+                self.buffer.drain(..BOM.len());
+            }
+        }
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        while let Some(offset) = self.buffer[start..]
+            .iter()
+            .position(|&b| b == b'\n' || b == b'\r')
+        {
+            let end = start + offset;
+            // A trailing \r may be the first half of \r\n; wait for the next byte
+            if self.buffer[end] == b'\r' && end + 1 == self.buffer.len() {
+                break;
+            }
+            let next = if self.buffer[end] == b'\r' && self.buffer[end + 1] == b'\n' {
+                end + 2
+            } else {
+                end + 1
+            };
+
+            let line = String::from_utf8_lossy(&self.buffer[start..end]).into_owned();
+            events.extend(self.process_line(&line));
+            start = next;
+        }
+        self.buffer.drain(..start);
+
+        events
+    }
+
+    /// End of body: an unterminated last line and an event missing its blank line
+    /// are still delivered
+    pub fn finish(&mut self) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&self.buffer)
+                .trim_end_matches('\r')
+                .to_string();
+            self.buffer.clear();
+            events.extend(self.process_line(&line));
+        }
+        events.extend(self.dispatch());
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "data" => {
+                let data = self.data.get_or_insert_with(String::new);
+                data.push_str(value);
+                data.push('\n');
+            }
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        let mut data = self.data.take()?;
+        data.pop();
+        Some(SseEvent {
+            event,
+            id: self.id.clone(),
+            data,
+        })
+    }
+}
+
+/// Decode a byte stream into events; the stream ends with the body or its first error
+pub fn events<S, B, E>(body: S) -> impl Stream<Item = Result<SseEvent, E>> + Send
+where
+    S: Stream<Item = Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]> + Send,
+    E: Send,
+{
+    async_stream::stream! {
+        let mut body = Box::pin(body);
+        let mut decoder = SseDecoder::new();
+
+        while let Some(chunk) = body.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    for event in decoder.feed(chunk.as_ref()) {
+                        yield Ok(event);
+                    }
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+
+        for event in decoder.finish() {
+            yield Ok(event);
+        }
+    }
+}
+
+/// Decode a complete body at once
+pub fn parse(body: &str) -> Vec<SseEvent> {
+    let mut decoder = SseDecoder::new();
+    let mut events = decoder.feed(body.as_bytes());
+    events.extend(decoder.finish());
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn data(events: &[SseEvent]) -> Vec<&str> {
+        events.iter().map(|e| e.data.as_str()).collect()
+    }
+
+    #[test]
+    fn fields_and_line_endings() {
+        let events = parse(
+            "\u{feff}: comment\r\nevent: ready\r\nid: 1\r\ndata: {\"a\":1}\r\n\r\n\
+             data: first\ndata:second\n\n\
+             event: close\rdata\r\r\
+             data: no blank line at the end",
+        );
+
+        assert_eq!(
+            events,
+            [
+                SseEvent {
+                    event: Some("ready".into()),
+                    id: Some("1".into()),
+                    data: "{\"a\":1}".into(),
+                },
+                SseEvent {
+                    event: None,
+                    id: Some("1".into()),
+                    data: "first\nsecond".into(),
+                },
+                SseEvent {
+                    event: Some("close".into()),
+                    id: Some("1".into()),
+                    data: String::new(),
+                },
+                SseEvent {
+                    event: None,
+                    id: Some("1".into()),
+                    data: "no blank line at the end".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn events_without_data_are_not_dispatched() {
+        let events = parse("event: ping\n\nid: 7\n\ndata: x\n\n");
+        assert_eq!(data(&events), ["x"]);
+        assert_eq!(events[0].event, None);
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+    }
+
+    /// `body` cut at each of `cuts`, taken modulo its length
+    fn split<'a>(body: &'a [u8], cuts: &[usize]) -> Vec<&'a [u8]> {
+        let mut cuts: Vec<usize> = cuts.iter().map(|c| c % (body.len() + 1)).collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        for cut in cuts.into_iter().chain([body.len()]) {
+            chunks.push(&body[start..cut]);
+            start = cut;
+        }
+        chunks
+    }
+
+    fn line_ending() -> impl Strategy<Value = &'static str> {
+        prop_oneof![Just("\n"), Just("\r\n"), Just("\r")]
+    }
+
+    proptest! {
+        #[test]
+        fn chunk_boundaries_do_not_matter(
+            payloads in prop::collection::vec("[a-z0-9 {}\":,é你好😀]{0,12}", 1..8),
+            ending in line_ending(),
+            cuts in prop::collection::vec(any::<usize>(), 0..16),
+        ) {
+            let body: String = payloads
+                .iter()
+                .map(|p| format!("event: e{ending}data: {p}{ending}{ending}"))
+                .collect();
+
+            let whole = parse(&body);
+            prop_assert_eq!(data(&whole), payloads.iter().map(String::as_str).collect::<Vec<_>>());
+
+            let mut decoder = SseDecoder::new();
+            let mut chunked = Vec::new();
+            for chunk in split(body.as_bytes(), &cuts) {
+                chunked.extend(decoder.feed(chunk));
+            }
+            chunked.extend(decoder.finish());
+            prop_assert_eq!(chunked, whole);
+        }
+
+        #[test]
+        fn arbitrary_bytes_never_panic(
+            body in prop::collection::vec(any::<u8>(), 0..256),
+            cuts in prop::collection::vec(any::<usize>(), 0..16),
+        ) {
+            let mut decoder = SseDecoder::new();
+            for chunk in split(&body, &cuts) {
+                decoder.feed(chunk);
+            }
+            decoder.finish();
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! The `api_server` binary against the mock upstream
+
+mod support;
+
+use serde_json::{json, Value};
+use std::time::Duration;
+use support::mock_qwen::{self, MockQwen};
+use support::ApiServer;
+
+/// Mock upstream plus a server that already has the mock token in its pool
+async fn start() -> (MockQwen, ApiServer, rquest::Client) {
+    let mock = MockQwen::start().await;
+    let server = ApiServer::start(mock.base_url()).await;
+    let http = rquest::Client::new();
+
+    let configured = http
+        .post(server.url("/v1/config/qwen"))
+        .json(&json!({ "token": mock_qwen::TOKEN }))
+        .send()
+        .await
+        .unwrap();
+    assert!(configured.status().is_success());
+
+    (mock, server, http)
+}
+
+async fn post_json(http: &rquest::Client, url: String, body: Value) -> (u16, Value) {
+    let response = http.post(url).json(&body).send().await.unwrap();
+    let status = response.status().as_u16();
+    (status, response.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn chat_completions() {
+    let (_mock, server, http) = start().await;
+
+    let (status, body) = post_json(
+        &http,
+        server.url("/v1/chat/completions"),
+        json!({ "model": "qwen3-max", "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await;
+    assert_eq!(status, 200, "{}", body);
+    let answer = body["choices"][0]["message"]["content"].as_str().unwrap();
+    assert!(answer.starts_with("Echo: "), "{}", answer);
+    assert!(answer.contains("hi"), "{}", answer);
+
+    let streamed = http
+        .post(server.url("/v1/chat/completions"))
+        .json(&json!({
+            "model": "qwen3-max",
+            "stream": true,
+            "messages": [{ "role": "user", "content": "hi" }]
+        }))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(streamed.contains("chat.completion.chunk"), "{}", streamed);
+    assert!(streamed.contains("Echo: "), "{}", streamed);
+    assert!(
+        streamed.trim_end().ends_with("data: [DONE]"),
+        "{}",
+        streamed
+    );
+}
+
+#[tokio::test]
+async fn upload_and_generate_image() {
+    let (mock, server, http) = start().await;
+
+    let form = rquest::multipart::Form::new().part(
+        "file",
+        rquest::multipart::Part::bytes(mock_qwen::PNG).file_name("photo.png"),
+    );
+    let uploaded: Value = http
+        .post(server.url("/v1/files/upload"))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(uploaded["file_class"], "vision", "{}", uploaded);
+    assert_eq!(
+        mock.object("mock-bucket/user-1/photo.png").as_deref(),
+        Some(mock_qwen::PNG)
+    );
+
+    let (status, image) = post_json(
+        &http,
+        server.url("/v1/images/generate"),
+        json!({ "prompt": "a cat", "download": true }),
+    )
+    .await;
+    assert_eq!(status, 200, "{}", image);
+    assert!(image["data"][0]["url"]
+        .as_str()
+        .unwrap()
+        .ends_with("/cdn/image-1.png"));
+
+    // download: true keeps a copy on the server
+    let media_url = image["data"][0]["media_url"].as_str().unwrap();
+    let media = http.get(server.url(media_url)).send().await.unwrap();
+    assert_eq!(media.status().as_u16(), 200);
+    assert_eq!(&media.bytes().await.unwrap()[..], mock_qwen::PNG);
+}
+
+#[tokio::test]
+async fn video_job_runs_in_the_background() {
+    let (_mock, server, http) = start().await;
+
+    let (status, job) = post_json(
+        &http,
+        server.url("/v1/videos/generate"),
+        json!({ "prompt": "waves" }),
+    )
+    .await;
+    assert_eq!(status, 202, "{}", job);
+    let job_url = server.url(&format!("/v1/videos/{}", job["id"].as_str().unwrap()));
+
+    for _ in 0..100 {
+        let job: Value = http
+            .get(&job_url)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        match job["status"].as_str().unwrap() {
+            "succeeded" => {
+                let video_url = job["video_url"].as_str().unwrap();
+                assert!(video_url.ends_with("/cdn/video-1.mp4"), "{}", job);
+                return;
+            }
+            "failed" | "cancelled" => panic!("video job ended early: {}", job),
+            _ => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+    panic!("video job did not finish");
+}
+
+#[tokio::test]
+async fn rejected_token_is_reported() {
+    let mock = MockQwen::start().await;
+    let server = ApiServer::start(mock.base_url()).await;
+    let http = rquest::Client::new();
+
+    // Without a token the request never reaches upstream
+    let (status, _) = post_json(
+        &http,
+        server.url("/v1/chat/completions"),
+        json!({ "model": "qwen3-max", "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    http.post(server.url("/v1/config/qwen"))
+        .json(&json!({ "token": "wrong-token" }))
+        .send()
+        .await
+        .unwrap();
+    let (status, body) = post_json(
+        &http,
+        server.url("/v1/chat/completions"),
+        json!({ "model": "qwen3-max", "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await;
+    assert_eq!(status, 401, "{}", body);
+    assert_eq!(body["error"]["type"], "authentication_error", "{}", body);
+    assert!(mock
+        .requests()
+        .iter()
+        .all(|r| r.path != "/api/v2/chat/completions"));
+}
+
+#[tokio::test]
+async fn upstream_errors_keep_their_status() {
+    let (_mock, server, http) = start().await;
+    let chat = |content: &str| json!({ "model": "qwen3-max", "messages": [{ "role": "user", "content": content }] });
+
+    let (status, body) = post_json(
+        &http,
+        server.url("/v1/chat/completions"),
+        chat("please [upstream error]"),
+    )
+    .await;
+    assert_eq!(status, 400, "{}", body);
+    assert_eq!(body["error"]["type"], "invalid_request_error", "{}", body);
+    assert_eq!(body["error"]["code"], "invalid_request", "{}", body);
+
+    let response = http
+        .post(server.url("/v1/chat/completions"))
+        .json(&chat("please [rate limited]"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(response.headers()["retry-after"], "7");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "rate_limit_error", "{}", body);
+    assert_eq!(body["error"]["code"], "rate_limited", "{}", body);
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("Too many requests"));
+}
+
+#[tokio::test]
+async fn storage_errors_do_not_bench_the_token() {
+    let (_mock, server, http) = start().await;
+
+    let form = rquest::multipart::Form::new().part(
+        "file",
+        rquest::multipart::Part::bytes(mock_qwen::PNG).file_name("oss-denied.png"),
+    );
+    let response = http
+        .post(server.url("/v1/files/upload"))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(status, 502, "{}", body);
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("SignatureDoesNotMatch"));
+
+    // The only token in the pool must still be usable
+    let (status, body) = post_json(
+        &http,
+        server.url("/v1/chat/completions"),
+        json!({ "model": "qwen3-max", "messages": [{ "role": "user", "content": "hi" }] }),
+    )
+    .await;
+    assert_eq!(status, 200, "{}", body);
+}
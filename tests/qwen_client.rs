@@ -0,0 +1,310 @@
+//! `QwenClient` against the mock upstream
+
+mod support;
+
+use futures_util::StreamExt;
+use reverse_api::qwen::client::modules::streaming::StreamingHandler;
+use reverse_api::recording::Exchange;
+use reverse_api::{
+    ChatRequest, ChatType, QwenClient, QwenError, QwenEvent, UploadLimits, VideoPollConfig,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+use support::mock_qwen::{self, MockQwen};
+
+fn client(mock: &MockQwen) -> QwenClient {
+    QwenClient::builder()
+        .token(mock_qwen::TOKEN)
+        .base_url(mock.base_url())
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn answers_and_continues_a_conversation() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock);
+
+    let first = client.start_convo("hello", None, None).await.unwrap();
+    assert_eq!(first.content, "Echo: hello");
+    assert_eq!(first.response_id, "response-1");
+    assert_eq!(first.chat_id.as_deref(), Some("chat-1"));
+
+    let extra = reverse_api::QwenExtraData {
+        chat_id: first.chat_id.clone().unwrap(),
+        model_id: "qwen3-max".into(),
+        parent_id: Some(first.response_id.clone()),
+    };
+    let second = client
+        .start_convo("again", None, Some(&extra))
+        .await
+        .unwrap();
+    assert_eq!(second.content, "Echo: again");
+
+    let completions: Vec<_> = mock
+        .requests()
+        .into_iter()
+        .filter(|r| r.path == "/api/v2/chat/completions")
+        .collect();
+    assert_eq!(completions.len(), 2);
+    assert_eq!(completions[1].body["chat_id"], "chat-1");
+    assert_eq!(completions[1].body["parent_id"], "response-1");
+}
+
+#[tokio::test]
+async fn thinking_and_search_phases() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock);
+
+    let thought = client
+        .start_convo_with_thinking("why", Some("qwen3-max"), None, Some(1024))
+        .await
+        .unwrap();
+    assert_eq!(
+        thought.thinking_content.as_deref(),
+        Some("Let me think about it.")
+    );
+    assert_eq!(thought.content, "Echo: why");
+
+    let searched = client
+        .start_convo_with_search("news", None, None)
+        .await
+        .unwrap();
+    let results = searched.web_search_results.unwrap();
+    assert_eq!(results[0].title, "Mock result");
+    assert_eq!(searched.content, "Echo: news");
+
+    // qwen3-vl-plus has no thinking capability, so the turn is refused before it is sent
+    let refused = client
+        .start_convo_with_thinking("why", Some("qwen3-vl-plus"), None, None)
+        .await;
+    assert!(matches!(refused, Err(QwenError::InvalidRequest(_))));
+}
+
+#[tokio::test]
+async fn deep_research_reports_its_steps() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock);
+
+    let response = client
+        .send(ChatRequest::new("rust", ChatType::DeepResearch))
+        .await
+        .unwrap();
+    assert_eq!(response.content, "Plan: search the web. Echo: rust");
+
+    let sent = mock
+        .requests()
+        .into_iter()
+        .find(|r| r.path == "/api/v2/chat/completions")
+        .unwrap();
+    let message = &sent.body["messages"][0];
+    assert_eq!(message["chat_type"], "deep_research");
+    assert_eq!(message["feature_config"]["research_mode"], "advance");
+}
+
+#[tokio::test]
+async fn streams_events_in_order() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock);
+
+    let request = ChatRequest::new("stream me", ChatType::TextToText).with_thinking(true);
+    let events: Vec<QwenEvent> = client
+        .send_stream(request)
+        .await
+        .unwrap()
+        .map(|event| event.unwrap())
+        .collect()
+        .await;
+
+    assert!(matches!(events[0], QwenEvent::ResponseCreated { .. }));
+    assert!(matches!(&events[1], QwenEvent::PhaseChanged(phase) if phase == "thinking"));
+    let answer: String = events
+        .iter()
+        .filter_map(|event| match event {
+            QwenEvent::AnswerDelta(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(answer, "Echo: stream me");
+    assert!(matches!(events.last(), Some(QwenEvent::Done)));
+}
+
+#[tokio::test]
+async fn upstream_error_event_fails_the_turn() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock);
+
+    let error = client
+        .start_convo("please [upstream error]", None, None)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Bad_Request"), "{}", error);
+    assert!(matches!(error, QwenError::InvalidRequest(_)), "{}", error);
+}
+
+#[tokio::test]
+async fn generates_images() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock);
+
+    let images = client
+        .generate_images("a cat", 2, Some("1:1"), None, None)
+        .await
+        .unwrap();
+    assert_eq!(images.images.len(), 2);
+    assert!(images.images[0].ends_with("/cdn/image-1.png"));
+    assert!(images.images[1].ends_with("/cdn/image-2.png"));
+
+    let bytes = client.fetch_media(&images.images[0]).await.unwrap();
+    assert_eq!(bytes, mock_qwen::PNG);
+}
+
+#[tokio::test]
+async fn uploads_to_the_oss_sink() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock);
+
+    let file = client
+        .upload_bytes("photo.png", mock_qwen::PNG.to_vec())
+        .await
+        .unwrap();
+    assert_eq!(file.id, "file-photo.png");
+    assert_eq!(file.file_class, "vision");
+    assert_eq!(
+        mock.object("mock-bucket/user-1/photo.png").as_deref(),
+        Some(mock_qwen::PNG)
+    );
+
+    let limited = client.with_upload_limits(UploadLimits {
+        vision: 4,
+        ..UploadLimits::default()
+    });
+    let too_big = limited
+        .upload_bytes("photo.png", mock_qwen::PNG.to_vec())
+        .await;
+    assert!(matches!(too_big, Err(QwenError::InvalidRequest(_))));
+}
+
+#[tokio::test]
+async fn polls_video_until_done() {
+    let mock = MockQwen::start().await;
+    let client = client(&mock).with_video_poll_config(VideoPollConfig {
+        interval: Duration::from_millis(10),
+        ..VideoPollConfig::default()
+    });
+
+    let task = client.start_video("waves", None, None, None).await.unwrap();
+    assert_eq!(task.task_id, "task-1");
+
+    let statuses = std::sync::Mutex::new(Vec::new());
+    let url = client
+        .wait_for_video(&task.task_id, |progress| {
+            statuses.lock().unwrap().push(progress.status.clone())
+        })
+        .await
+        .unwrap();
+    assert!(url.ends_with("/cdn/video-1.mp4"));
+    assert_eq!(*statuses.lock().unwrap(), ["running", "success"]);
+}
+
+#[tokio::test]
+async fn signs_in_and_rejects_bad_tokens() {
+    let mock = MockQwen::start().await;
+
+    let signed_in = QwenClient::builder()
+        .credentials(mock_qwen::EMAIL, mock_qwen::PASSWORD)
+        .base_url(mock.base_url())
+        .build()
+        .unwrap();
+    assert_eq!(signed_in.get_models().await.unwrap().len(), 2);
+
+    let rejected = QwenClient::builder()
+        .token("wrong-token")
+        .base_url(mock.base_url())
+        .build()
+        .unwrap();
+    assert!(matches!(
+        rejected.get_models().await,
+        Err(QwenError::Unauthorized(_))
+    ));
+
+    let wrong_password = QwenClient::builder()
+        .credentials(mock_qwen::EMAIL, "not-the-password")
+        .base_url(mock.base_url())
+        .build()
+        .unwrap();
+    assert!(matches!(
+        wrong_password.get_models().await,
+        Err(QwenError::Unauthorized(_))
+    ));
+}
+
+#[tokio::test]
+async fn shared_http_client_keeps_accounts_apart() {
+    let mock = MockQwen::start().await;
+    let template = QwenClient::builder()
+        .base_url(mock.base_url())
+        .share_http_client()
+        .unwrap();
+    let first = template.clone().token(mock_qwen::TOKEN).build().unwrap();
+    let second = template.clone().token("other-token").build().unwrap();
+
+    first.get_models().await.unwrap();
+    let _ = second.get_models().await;
+    first.get_models().await.unwrap();
+
+    // A client of its own still keeps its session cookie
+    let own = client(&mock);
+    own.get_models().await.unwrap();
+    own.get_models().await.unwrap();
+
+    let cookies: Vec<Value> = mock
+        .requests()
+        .into_iter()
+        .filter(|r| r.path == "/api/models")
+        .map(|r| r.body["cookie"].clone())
+        .collect();
+    assert_eq!(
+        cookies,
+        [
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            json!(format!("mock-session={}", mock_qwen::TOKEN)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn records_a_replayable_fixture() {
+    let mock = MockQwen::start().await;
+    let dir = std::env::temp_dir().join(format!("qwen-recording-{}", uuid::Uuid::new_v4()));
+    let client = QwenClient::builder()
+        .token(mock_qwen::TOKEN)
+        .base_url(mock.base_url())
+        .record_to(&dir)
+        .build()
+        .unwrap();
+
+    let answer = client.start_convo("record me", None, None).await.unwrap();
+
+    let written = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap();
+    let exchange = Exchange::load(written.path()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(written
+        .file_name()
+        .to_string_lossy()
+        .starts_with("qwen-completion-"));
+    assert_eq!(exchange.request.headers["authorization"], "[redacted]");
+    assert_eq!(
+        exchange.request.body.as_ref().unwrap()["messages"][0]["content"],
+        "record me"
+    );
+
+    let replayed = StreamingHandler::collect(StreamingHandler::decode(exchange.replay(3)), |_| {})
+        .await
+        .unwrap();
+    assert_eq!(replayed.content, answer.content);
+}
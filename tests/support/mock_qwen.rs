@@ -0,0 +1,440 @@
+//! In-process stand-in for chat.qwen.ai, its OSS bucket and its CDN.
+//!
+//! Completions are scripted from the request: `thinking_enabled` adds a thinking phase,
+//! `search` chats get web search results, `deep_research` chats a planning step, `t2i`/`image_edit` chats produce a new image
+//! each time and every other chat answers `Echo: <message>`. A message containing `[upstream error]`
+//! gets an SSE error event instead, one containing `[rate limited]` a 429. Uploads of a
+//! file named `oss-denied*` are refused by the OSS sink with a 403. Video tasks report `running` on the first poll and
+//! `success` afterwards.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// The only token the mock accepts
+pub const TOKEN: &str = "mock-token";
+pub const EMAIL: &str = "mock@example.com";
+pub const PASSWORD: &str = "mock-password";
+
+/// First bytes of a PNG, enough for content sniffing
+pub const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0";
+/// First bytes of an MP4
+pub const MP4: &[u8] = b"\0\0\0\x18ftypmp42\0\0\0\0mp42isom";
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    base_url: String,
+    requests: Mutex<Vec<RecordedRequest>>,
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+    task_polls: Mutex<HashMap<String, u32>>,
+    chats: Mutex<u32>,
+    images: Mutex<u32>,
+}
+
+impl MockState {
+    fn next_image_url(&self) -> String {
+        let mut images = self.images.lock().unwrap();
+        *images += 1;
+        format!("{}/cdn/image-{}.png", self.base_url, images)
+    }
+
+    fn record(&self, method: &str, path: &str, body: Value) {
+        self.requests.lock().unwrap().push(RecordedRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            body,
+        });
+    }
+}
+
+pub struct MockQwen {
+    state: Arc<MockState>,
+    server: JoinHandle<()>,
+}
+
+impl MockQwen {
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let state = Arc::new(MockState {
+            base_url: format!("http://{}", listener.local_addr().unwrap()),
+            ..Default::default()
+        });
+
+        let app = Router::new()
+            .route("/api/v1/auths/signin", post(signin))
+            .route("/api/models", get(models))
+            .route("/api/v1/users/user/settings", get(user_settings))
+            .route("/api/v2/chats/new", post(new_chat))
+            .route("/api/v2/chat/completions", post(completions))
+            .route("/api/v2/files/getstsToken", post(sts_token))
+            .route("/api/v1/tasks/status/{task_id}", get(task_status))
+            .route("/oss/{bucket}/{*path}", put(oss_put))
+            .route("/cdn/{name}", get(cdn))
+            .with_state(state.clone());
+
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { state, server }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.state.base_url
+    }
+
+    /// Every API request received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Bytes uploaded to the OSS sink under `bucket/path`
+    pub fn object(&self, key: &str) -> Option<Vec<u8>> {
+        self.state.objects.lock().unwrap().get(key).cloned()
+    }
+}
+
+impl Drop for MockQwen {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// 401 response unless the request carries [`TOKEN`]
+fn unauthorized(headers: &HeaderMap) -> Option<Response> {
+    let expected = format!("Bearer {}", TOKEN);
+    if headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        == Some(&expected)
+    {
+        return None;
+    }
+    Some(
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({
+                "success": false,
+                "data": { "code": "Unauthorized", "details": "Invalid token" }
+            })),
+        )
+            .into_response(),
+    )
+}
+
+async fn signin(State(state): State<Arc<MockState>>, Json(body): Json<Value>) -> Response {
+    state.record(
+        "POST",
+        "/api/v1/auths/signin",
+        json!({ "email": body["email"] }),
+    );
+    if body["email"] != EMAIL || body["password"] != PASSWORD {
+        return (StatusCode::BAD_REQUEST, "Invalid credentials").into_response();
+    }
+    Json(json!({
+        "id": "user-1",
+        "email": EMAIL,
+        "name": "Mock",
+        "role": "user",
+        "token": TOKEN,
+        "token_type": "Bearer"
+    }))
+    .into_response()
+}
+
+fn model(id: &str, thinking: bool, vision: bool) -> Value {
+    json!({
+        "id": id,
+        "name": id,
+        "object": "model",
+        "owned_by": "qwen",
+        "info": {
+            "id": id,
+            "name": id,
+            "meta": {
+                "capabilities": {
+                    "vision": vision,
+                    "document": true,
+                    "thinking": thinking,
+                    "thinking_budget": thinking
+                },
+                "chat_type": ["t2t", "search", "t2i", "t2v", "image_edit"],
+                "max_context_length": 131072,
+                "max_thinking_generation_length": if thinking { 38912 } else { 0 }
+            }
+        }
+    })
+}
+
+/// Also hands out a session cookie named after the token, and records the cookie the
+/// request came with as `{"cookie": ...}`
+async fn models(State(state): State<Arc<MockState>>, headers: HeaderMap) -> Response {
+    let header_value = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    state.record(
+        "GET",
+        "/api/models",
+        json!({ "cookie": header_value(header::COOKIE) }),
+    );
+    let session = format!(
+        "mock-session={}; Path=/",
+        header_value(header::AUTHORIZATION)
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or_default()
+    );
+
+    let response = match unauthorized(&headers) {
+        Some(rejected) => rejected,
+        None => Json(json!({
+            "data": [model("qwen3-max", true, false), model("qwen3-vl-plus", false, true)]
+        }))
+        .into_response(),
+    };
+    ([(header::SET_COOKIE, session)], response).into_response()
+}
+
+async fn user_settings(headers: HeaderMap) -> Response {
+    if let Some(rejected) = unauthorized(&headers) {
+        return rejected;
+    }
+    Json(json!({ "id": "user-1" })).into_response()
+}
+
+async fn new_chat(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    if let Some(rejected) = unauthorized(&headers) {
+        return rejected;
+    }
+    state.record("POST", "/api/v2/chats/new", body);
+    let id = {
+        let mut chats = state.chats.lock().unwrap();
+        *chats += 1;
+        format!("chat-{}", chats)
+    };
+    Json(json!({ "success": true, "request_id": "req-1", "data": { "id": id } })).into_response()
+}
+
+async fn completions(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+    Json(body): Json<Value>,
+) -> Response {
+    if let Some(rejected) = unauthorized(&headers) {
+        return rejected;
+    }
+    state.record("POST", "/api/v2/chat/completions", body.clone());
+
+    let chat_id = query.get("chat_id").cloned().unwrap_or_default();
+    let message = &body["messages"][0];
+
+    // Video tasks are submitted without streaming and polled afterwards
+    if body["stream"] == false {
+        return Json(json!({
+            "success": true,
+            "data": {
+                "message_id": "video-message-1",
+                "messages": [{ "extra": { "wanx": { "task_id": "task-1" } } }],
+                "chat_id": chat_id,
+                "parent_id": "video-parent-1"
+            }
+        }))
+        .into_response();
+    }
+
+    if message["content"]
+        .as_str()
+        .is_some_and(|c| c.contains("[rate limited]"))
+    {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, "7")],
+            Json(json!({
+                "success": false,
+                "data": { "code": "RateLimited", "details": "Too many requests" }
+            })),
+        )
+            .into_response();
+    }
+
+    let lines = completion_lines(&state, &chat_id, message);
+    let body: String = lines
+        .iter()
+        .map(|line| format!("data: {}\n\n", line))
+        .collect();
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn delta(phase: &str, content: &str) -> Value {
+    json!({ "choices": [{ "delta": { "role": "assistant", "phase": phase, "content": content } }] })
+}
+
+fn completion_lines(state: &MockState, chat_id: &str, message: &Value) -> Vec<Value> {
+    let content = message["content"].as_str().unwrap_or_default();
+    let chat_type = message["chat_type"].as_str().unwrap_or("t2t");
+
+    if content.contains("[upstream error]") {
+        return vec![json!({
+            "error": { "code": "Bad_Request", "details": "The mock was asked to fail" }
+        })];
+    }
+
+    let mut lines = vec![json!({
+        "response.created": { "chat_id": chat_id, "response_id": "response-1" }
+    })];
+
+    if message["feature_config"]["thinking_enabled"] == true {
+        lines.push(delta("thinking", "Let me think"));
+        lines.push(delta("thinking", " about it."));
+    }
+
+    match chat_type {
+        "search" => {
+            lines.push(json!({
+                "choices": [{ "delta": {
+                    "phase": "web_search",
+                    "extra": { "web_search_info": [{
+                        "url": "https://example.com/result",
+                        "title": "Mock result",
+                        "snippet": "A search result from the mock"
+                    }] }
+                } }]
+            }));
+        }
+        "deep_research" => lines.push(delta("ResearchPlanning", "Plan: search the web. ")),
+        "t2i" | "image_edit" => {
+            lines.push(delta("image_gen", &state.next_image_url()));
+            lines.push(
+                json!({ "choices": [{ "delta": { "phase": "image_gen", "status": "finished" } }] }),
+            );
+            return lines;
+        }
+        _ => {}
+    }
+
+    lines.push(delta("answer", "Echo: "));
+    lines.push(delta("answer", content));
+    lines.push(json!({ "choices": [{ "delta": { "phase": "answer", "status": "finished" } }] }));
+    lines
+}
+
+async fn sts_token(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    if let Some(rejected) = unauthorized(&headers) {
+        return rejected;
+    }
+    state.record("POST", "/api/v2/files/getstsToken", body.clone());
+
+    let filename = body["filename"].as_str().unwrap_or("file");
+    let file_path = format!("user-1/{}", filename);
+    Json(json!({
+        "success": true,
+        "request_id": "req-1",
+        "data": {
+            "access_key_id": "mock-key",
+            "access_key_secret": "mock-secret",
+            "security_token": "mock-security-token",
+            "file_url": format!("{}/oss/mock-bucket/{}", state.base_url, file_path),
+            "file_path": file_path,
+            "file_id": format!("file-{}", filename),
+            "bucketname": "mock-bucket",
+            "region": "oss-mock",
+            "endpoint": format!("{}/oss", state.base_url)
+        }
+    }))
+    .into_response()
+}
+
+async fn oss_put(
+    State(state): State<Arc<MockState>>,
+    Path((bucket, path)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let signed = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("OSS4-HMAC-SHA256 Credential=mock-key/"));
+    if !signed {
+        return (StatusCode::FORBIDDEN, "Missing OSS signature").into_response();
+    }
+    if path.contains("oss-denied") {
+        return (
+            StatusCode::FORBIDDEN,
+            [(header::CONTENT_TYPE, "application/xml")],
+            "<Error><Code>SignatureDoesNotMatch</Code>\
+             <Message>The request signature we calculated does not match</Message></Error>",
+        )
+            .into_response();
+    }
+    state
+        .objects
+        .lock()
+        .unwrap()
+        .insert(format!("{}/{}", bucket, path), body.to_vec());
+    StatusCode::OK.into_response()
+}
+
+async fn task_status(
+    State(state): State<Arc<MockState>>,
+    Path(task_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(rejected) = unauthorized(&headers) {
+        return rejected;
+    }
+    let polls = {
+        let mut task_polls = state.task_polls.lock().unwrap();
+        let polls = task_polls.entry(task_id).or_default();
+        *polls += 1;
+        *polls
+    };
+
+    let (status, remaining, content) = if polls == 1 {
+        ("running", "1s", String::new())
+    } else {
+        ("success", "", format!("{}/cdn/video-1.mp4", state.base_url))
+    };
+    Json(json!({
+        "chat_type": "t2v",
+        "task_status": status,
+        "message": "",
+        "remaining_time": remaining,
+        "content": content
+    }))
+    .into_response()
+}
+
+async fn cdn(Path(name): Path<String>) -> Response {
+    let (mime, bytes) = if name.ends_with(".mp4") {
+        ("video/mp4", MP4)
+    } else {
+        ("image/png", PNG)
+    };
+    ([(header::CONTENT_TYPE, mime)], bytes).into_response()
+}
@@ -0,0 +1,78 @@
+//! Helpers shared by the integration tests. Nothing here reaches the network:
+//! upstream is replaced by [`mock_qwen::MockQwen`] on a loopback port.
+
+// Each test crate uses a different subset of the helpers
+#![allow(dead_code)]
+
+pub mod mock_qwen;
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+/// `api_server` binary of this package, run in a scratch directory against `qwen_base_url`
+pub struct ApiServer {
+    pub base_url: String,
+    dir: PathBuf,
+    _process: tokio::process::Child,
+}
+
+impl ApiServer {
+    pub async fn start(qwen_base_url: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("api-server-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        // The scratch directory keeps .qwen_token, .deepseek_token and media of the
+        // developer's checkout out of the test
+        let process = tokio::process::Command::new(env!("CARGO_BIN_EXE_api_server"))
+            .args(["--host", "127.0.0.1", "--port", &port.to_string()])
+            .args(["--qwen-base-url", qwen_base_url])
+            .current_dir(&dir)
+            .env_remove("API_KEYS")
+            .env_remove("API_HOST")
+            .env_remove("API_PORT")
+            .env_remove("DEEPSEEK_TOKEN")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("api_server should start");
+
+        let server = Self {
+            base_url: format!("http://127.0.0.1:{}", port),
+            dir,
+            _process: process,
+        };
+        server.wait_until_ready().await;
+        server
+    }
+
+    async fn wait_until_ready(&self) {
+        let client = rquest::Client::new();
+        for _ in 0..100 {
+            if let Ok(response) = client.get(self.url("/health")).send().await {
+                if response.status().is_success() {
+                    return;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("api_server did not become ready at {}", self.base_url);
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}